@@ -0,0 +1,142 @@
+//! Answers accepted by the website, recorded in `answers/<day>.toml`
+//! so that solvers can be verified without network access.
+//!
+//! Only a tiny subset of TOML is understood:
+//!
+//! ```text
+//! # comments and blank lines are ignored
+//! part1 = "1234"
+//! part2 = 5678
+//! ```
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::days::Day;
+use crate::records::{load_records, parse_records};
+use super::*;
+
+peg::parser!{
+
+	grammar answers() for str {
+
+		rule _ = [' ' | '\t']*
+
+		rule comment() = "#" [_]*
+
+		rule part() -> u8
+			= "part" n:$(['0'..='9']+) {? n.parse().or(Err("Expected part number")) }
+
+		rule quoted() -> &'input str
+			= "\"" s:$([^'"']*) "\"" { s }
+
+		rule bare() -> &'input str
+			= $("-"? ['0'..='9']+)
+
+		/// Matches a `partN = answer` line, or a blank/comment line
+		pub rule entry() -> Option<(u8,&'input str)>
+			= _ p:part() _ "=" _ a:(quoted() / bare()) _ comment()? { Some((p,a)) }
+			/ _ comment()? { None }
+	}
+}
+
+/// Outcome of checking a solver output against a recorded answer
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+	Pass,
+	Fail { expected: String, actual: String },
+	Unknown(String),
+	/// The solver failed, for the given reason
+	Error(String),
+}
+
+impl Display for Verdict {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Verdict::Pass => write!(f, "pass"),
+			Verdict::Fail { expected, actual } => write!(f, "FAIL (expected {expected}, got {actual})"),
+			Verdict::Unknown(reason) => write!(f, "unknown ({reason})"),
+			Verdict::Error(reason) => write!(f, "FAIL ({reason})"),
+		}
+	}
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Answers(BTreeMap<u8,String>);
+
+impl Answers {
+
+	pub fn path(day:Day) -> String {
+		format!("answers/{day}.toml")
+	}
+
+	/// Reads the answers recorded for a day, none when there's no file yet
+	pub fn load(day:Day) -> Result<Self,AppError> {
+		load_records(&Self::path(day), Self::parse)
+	}
+
+	pub fn parse(content:&str) -> Result<Self,String> {
+		parse_records(content, |line| {
+			answers::entry(line).map(|entry| entry.map(|(part,answer)| (part,answer.to_string())))
+		})
+		.map(Self)
+	}
+
+	pub fn get(&self, part:u8) -> Option<&str> {
+		self.0.get(&part).map(String::as_str)
+	}
+
+	pub fn check(&self, part:u8, actual:String) -> Verdict {
+		match self.get(part) {
+			None => Verdict::Unknown("no recorded answer".into()),
+			Some(expected) if expected == actual => Verdict::Pass,
+			Some(expected) => Verdict::Fail { expected: expected.into(), actual },
+		}
+	}
+
+	/// Stores an accepted answer in the day's answers file
+	pub fn record(day:Day, part:u8, answer:&str) -> Result<(),AppError> {
+		let mut answers = Self::load(day)?;
+		answers.0.insert(part, answer.into());
+
+		std::fs::create_dir_all("answers")
+			.and_then(|_| std::fs::write(Self::path(day), answers.to_string()))
+			.map_err(|e| AppError::BadConfiguration(format!("Can't record answer: {e}")))
+	}
+}
+
+impl Display for Answers {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		self.0.iter().try_for_each(|(part,answer)| writeln!(f, "part{part} = \"{answer}\""))
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn parse() {
+
+		const CONTENT:&str = r###"
+# Day 1
+part1 = "11"
+  part2=31   # trailing comment
+"###;
+
+		let answers = Answers::parse(CONTENT).unwrap();
+		assert_eq!(answers.get(1), Some("11"));
+		assert_eq!(answers.get(2), Some("31"));
+		assert_eq!(answers.get(3), None);
+
+		assert_eq!(answers.check(1, "11".into()), Verdict::Pass);
+		assert!(matches!(answers.check(2, "32".into()), Verdict::Fail { .. }));
+		assert_eq!(Verdict::Error("Unsolvable input: no way".into()).to_string(), "FAIL (Unsolvable input: no way)");
+
+		// round trip
+		assert_eq!(Answers::parse(&answers.to_string()).unwrap(), answers);
+
+		assert!(Answers::parse("part1 = ").is_err());
+		assert!(Answers::parse("answer = 1").is_err());
+	}
+}
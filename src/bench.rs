@@ -0,0 +1,226 @@
+//! Timing of the solvers, run repeatedly against the puzzle inputs.
+//!
+//! Results can be stored as a baseline in `bench/baseline.txt`,
+//! so that later runs flag the parts that became slower, or
+//! started allocating more:
+//!
+//! ```text
+//! # day part median(ns) allocations
+//! 1 1 105230 12
+//! 1 2 98311 14
+//! ```
+
+use std::{
+	alloc::{GlobalAlloc, Layout, System},
+	collections::BTreeMap,
+	fmt::Display,
+	sync::atomic::{AtomicUsize, Ordering},
+	time::{Duration, Instant},
+};
+
+use crate::days::{Day, Solver};
+use crate::records::{load_records, parse_records};
+use super::*;
+
+/// System allocator, keeping count of the allocations made.
+///
+/// It's the allocator of every command, not just `bench`, so that
+/// the counts compared to the baseline come from the same binary
+/// as the answers. Counting is a relaxed atomic increment, next to
+/// nothing compared to the allocation itself.
+pub struct Counting;
+
+static ALLOCATIONS:AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		unsafe { System.alloc(layout) }
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		unsafe { System.dealloc(ptr, layout) }
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+		unsafe { System.realloc(ptr, layout, new_size) }
+	}
+}
+
+fn allocations() -> usize {
+	ALLOCATIONS.load(Ordering::Relaxed)
+}
+
+/// A median time above the baseline by more than this ratio is a regression
+const TOLERANCE:f64 = 0.10;
+
+/// Timings of several runs of a solver
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Measure {
+	pub min: Duration,
+	pub median: Duration,
+	pub max: Duration,
+	/// Allocations made by a single run
+	pub allocations: usize,
+}
+
+impl Measure {
+
+	/// Runs a solver against an input, a number of times
+	pub fn take(solver:Solver, input:&str, runs:usize) -> Result<Self,AppError> {
+
+		let mut times = Vec::with_capacity(runs);
+		let mut allocated = usize::MAX;
+
+		for _ in 0..runs.max(1) {
+			let before = allocations();
+			let start = Instant::now();
+			solver(input)?;
+			times.push(start.elapsed());
+			// other threads may allocate meanwhile, the least is the most accurate
+			allocated = allocated.min(allocations() - before);
+		}
+
+		times.sort();
+
+		Ok(Measure {
+			min: times[0],
+			median: times[times.len()/2],
+			max: times[times.len()-1],
+			allocations: allocated,
+		})
+	}
+
+	/// Describes how this measure got worse than a previous one, if it did
+	pub fn regression(&self, baseline:&Measure) -> Option<String> {
+
+		let ratio = self.median.as_secs_f64() / baseline.median.as_secs_f64();
+
+		if ratio > 1.0 + TOLERANCE {
+			Some(format!("{:.0}% slower", (ratio - 1.0) * 100.0))
+		} else if self.allocations > baseline.allocations {
+			Some(format!("{} more allocations", self.allocations - baseline.allocations))
+		} else {
+			None
+		}
+	}
+}
+
+impl Display for Measure {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let Measure { min, median, max, allocations } = self;
+		write!(f, "min {min:>10.2?}  median {median:>10.2?}  max {max:>10.2?}  {allocations:>8} allocs")
+	}
+}
+
+peg::parser!{
+
+	grammar baseline() for str {
+
+		rule _ = [' ' | '\t']*
+		rule __ = [' ' | '\t']+
+
+		rule comment() = "#" [_]*
+
+		rule number() -> usize
+			= n:$(['0'..='9']+) {? n.parse().or(Err("Expected usize value")) }
+
+		/// Matches a `day part median allocations` line, or a blank/comment line
+		pub rule entry() -> Option<((Day,u8),Measure)>
+			= _ d:number() __ p:number() __ m:number() __ a:number() _ comment()? {?
+				let part = u8::try_from(p).or(Err("Expected part number"))?;
+				let median = Duration::from_nanos(m as u64);
+				let measure = Measure { min: median, median, max: median, allocations: a };
+				Ok(Some(((Day(d),part),measure)))
+			}
+			/ _ comment()? { None }
+	}
+}
+
+/// Measures taken earlier, to compare against
+#[derive(Debug, Default, PartialEq)]
+pub struct Baseline(BTreeMap<(Day,u8),Measure>);
+
+impl Baseline {
+
+	pub const PATH:&str = "bench/baseline.txt";
+
+	/// Reads the stored baseline, empty when nothing was saved yet
+	pub fn load() -> Result<Self,AppError> {
+		load_records(Self::PATH, Self::parse)
+	}
+
+	pub fn parse(content:&str) -> Result<Self,String> {
+		parse_records(content, baseline::entry).map(Self)
+	}
+
+	pub fn get(&self, day:Day, part:u8) -> Option<&Measure> {
+		self.0.get(&(day,part))
+	}
+
+	pub fn insert(&mut self, day:Day, part:u8, measure:Measure) {
+		self.0.insert((day,part), measure);
+	}
+
+	pub fn save(&self) -> Result<(),AppError> {
+		std::fs::create_dir_all("bench")
+			.and_then(|_| std::fs::write(Self::PATH, self.to_string()))
+			.map_err(|e| AppError::BadConfiguration(format!("Can't save baseline: {e}")))
+	}
+}
+
+impl Display for Baseline {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "# day part median(ns) allocations")?;
+		self.0.iter().try_for_each(|((day,part),m)| {
+			writeln!(f, "{day} {part} {} {}", m.median.as_nanos(), m.allocations)
+		})
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn measure() {
+
+		let solver:Solver = |input| Ok(input.repeat(2));
+		let m = Measure::take(solver, "abc", 5).unwrap();
+
+		assert!(m.min <= m.median && m.median <= m.max);
+		assert!(m.allocations >= 1);
+
+		let slower = Measure { median: m.median * 2 + Duration::from_micros(1), ..m };
+		assert!(slower.regression(&m).unwrap().ends_with("slower"));
+		assert!(m.regression(&slower).is_none());
+
+		let greedier = Measure { allocations: m.allocations + 3, ..m };
+		assert_eq!(greedier.regression(&m).unwrap(), "3 more allocations");
+
+		let failing:Solver = |_| Err(AppError::Unsolvable("nope".into()));
+		assert!(Measure::take(failing, "", 5).is_err());
+	}
+
+	#[test]
+	fn baseline() {
+
+		const CONTENT:&str = r###"
+# day part median(ns) allocations
+1 1 105230 12
+11 2  98311 14   # trailing comment
+"###;
+
+		let baseline = Baseline::parse(CONTENT).unwrap();
+		assert_eq!(baseline.get(Day(1), 1).unwrap().allocations, 12);
+		assert_eq!(baseline.get(Day(11), 2).unwrap().median, Duration::from_nanos(98311));
+		assert!(baseline.get(Day(1), 2).is_none());
+
+		// round trip
+		assert_eq!(Baseline::parse(&baseline.to_string()).unwrap(), baseline);
+
+		assert!(Baseline::parse("1 1 100").is_err());
+	}
+}
@@ -0,0 +1,372 @@
+use std::{fs::File, io::BufReader, path::{Path, PathBuf}, thread, time::Duration};
+
+use itertools::Itertools;
+
+use crate::answers::Verdict;
+use crate::bench::{Baseline, Measure};
+use crate::days::{self, Day};
+use super::*;
+
+pub const USAGE:&str = "\
+Usage:
+  advent-of-code-2024 run <day> [--part 1|2] [--input FILE]
+  advent-of-code-2024 submit <day> <part>
+  advent-of-code-2024 verify [<day>]
+  advent-of-code-2024 animate <day> [--part 1|2] [--input FILE] [--delay MS]
+  advent-of-code-2024 bench [<day>] [--runs N] [--save]
+  advent-of-code-2024 all
+  advent-of-code-2024 list";
+
+/// A request parsed from the command line
+#[derive(Debug, PartialEq)]
+pub enum Command {
+	/// Solve one or both parts of a day, and print the answers
+	Run { day: Day, part: Option<u8>, input: Option<PathBuf> },
+	/// Solve a part of a day, and post the answer
+	Submit { day: Day, part: u8 },
+	/// Check solvers against the locally recorded answers
+	Verify { day: Option<Day> },
+	/// Replay the solution of a part in the terminal, frame by frame
+	Animate { day: Day, part: u8, input: Option<PathBuf>, delay: Duration },
+	/// Time solvers, comparing against a stored baseline
+	Bench { day: Option<Day>, runs: usize, save: bool },
+	/// Solve every implemented part of every day
+	All,
+	/// Show which days and parts are implemented
+	List,
+}
+
+fn bad_usage(msg:impl Into<String>) -> AppError {
+	AppError::BadUsage(msg.into())
+}
+
+fn parse_day(arg:Option<String>) -> Result<Day,AppError> {
+	let arg = arg.ok_or_else(|| bad_usage("Missing <day> argument"))?;
+	match arg.parse::<usize>() {
+		Ok(n @ 1..=25) => Ok(Day(n)),
+		_ => Err(bad_usage(format!("Invalid day '{arg}', expected a number from 1 to 25")))
+	}
+}
+
+fn parse_part(arg:Option<String>) -> Result<u8,AppError> {
+	let arg = arg.ok_or_else(|| bad_usage("Missing <part> argument"))?;
+	match arg.parse::<u8>() {
+		Ok(n @ 1..=2) => Ok(n),
+		_ => Err(bad_usage(format!("Invalid part '{arg}', expected 1 or 2")))
+	}
+}
+
+fn parse_file(arg:Option<String>) -> Result<PathBuf,AppError> {
+	arg.map(PathBuf::from).ok_or_else(|| bad_usage("Missing FILE argument"))
+}
+
+fn cant_read(path:&Path, e:std::io::Error) -> AppError {
+	AppError::MissingInput(format!("Can't read {}: {e}", path.display()))
+}
+
+/// Reads the input from a file, when given, or else the day's puzzle input
+fn read_input(day:Day, input:Option<&Path>) -> Result<String,AppError> {
+	match input {
+		Some(path) => std::fs::read_to_string(path).map_err(|e| cant_read(path, e)),
+		None => days::input_for(day)
+	}
+}
+
+/// Opens a file for a solver to read line by line
+fn open_input(path:&Path) -> Result<BufReader<File>,AppError> {
+	File::open(path).map(BufReader::new).map_err(|e| cant_read(path, e))
+}
+
+impl Command {
+
+	pub fn parse(mut args:impl Iterator<Item=String>) -> Result<Self,AppError> {
+
+		let command = args.next().ok_or_else(|| bad_usage("Missing command"))?;
+
+		let parsed = match command.as_str() {
+
+			"run" => {
+				let day = parse_day(args.next())?;
+				let mut part = None;
+				let mut input = None;
+
+				while let Some(flag) = args.next() {
+					match flag.as_str() {
+						"--part"  => part  = Some(parse_part(args.next())?),
+						"--input" => input = Some(parse_file(args.next())?),
+						_ => return Err(bad_usage(format!("Unknown option '{flag}'")))
+					}
+				}
+
+				Command::Run { day, part, input }
+			}
+
+			"submit" => {
+				let day = parse_day(args.next())?;
+				let part = parse_part(args.next())?;
+				Command::Submit { day, part }
+			}
+
+			"verify" => {
+				let day = args.next().map(|d| parse_day(Some(d))).transpose()?;
+				Command::Verify { day }
+			}
+
+			"animate" => {
+				let day = parse_day(args.next())?;
+				let mut part = 1;
+				let mut input = None;
+				let mut delay = Duration::from_millis(50);
+
+				while let Some(flag) = args.next() {
+					match flag.as_str() {
+						"--part"  => part  = parse_part(args.next())?,
+						"--input" => input = Some(parse_file(args.next())?),
+						"--delay" => {
+							let ms = args.next().ok_or_else(|| bad_usage("Missing MS argument"))?;
+							let ms = ms.parse().map_err(|_| bad_usage(format!("Invalid delay '{ms}', expected milliseconds")))?;
+							delay = Duration::from_millis(ms);
+						}
+						_ => return Err(bad_usage(format!("Unknown option '{flag}'")))
+					}
+				}
+
+				Command::Animate { day, part, input, delay }
+			}
+
+			"bench" => {
+				let mut day = None;
+				let mut runs = 10;
+				let mut save = false;
+
+				while let Some(arg) = args.next() {
+					match arg.as_str() {
+						"--runs" => {
+							let n = args.next().ok_or_else(|| bad_usage("Missing N argument"))?;
+							runs = match n.parse::<usize>() {
+								Ok(n @ 1..) => n,
+								_ => return Err(bad_usage(format!("Invalid runs '{n}', expected a positive number")))
+							}
+						}
+						"--save" => save = true,
+						_ if day.is_none() && !arg.starts_with("--") => day = Some(parse_day(Some(arg))?),
+						_ => return Err(bad_usage(format!("Unknown option '{arg}'")))
+					}
+				}
+
+				Command::Bench { day, runs, save }
+			}
+
+			"all" => Command::All,
+
+			"list" => Command::List,
+
+			_ => return Err(bad_usage(format!("Unknown command '{command}'")))
+		};
+
+		if let Some(extra) = args.next() {
+			return Err(bad_usage(format!("Unexpected argument '{extra}'")))
+		}
+
+		Ok(parsed)
+	}
+
+	pub fn execute(self) -> Result<(),AppError> {
+
+		match self {
+
+			Command::Run { day, part, input } => {
+
+				let parts = part.map(|p| vec![p]).unwrap_or(vec![1,2]);
+
+				for p in parts {
+					let answer = match (&input, days::stream_solver(day, p)) {
+						// line oriented days read a given file as they go,
+						// so that it does not have to fit in memory
+						(Some(path), Some(stream)) => stream(&mut open_input(path)?)?,
+						(input, _) => {
+							let solver = days::solver(day, p).ok_or_else(|| {
+								AppError::MissingSolver(format!("Day {day} part {p} is not implemented"))
+							})?;
+							solver(&read_input(day, input.as_deref())?)?
+						}
+					};
+					println!("Day {day:>2} part {p}: {answer}");
+				}
+
+				Ok(())
+			}
+
+			Command::Submit { day, part: p } => {
+
+				let solver = days::solver(day, p).ok_or_else(|| {
+					AppError::MissingSolver(format!("Day {day} part {p} is not implemented"))
+				})?;
+
+				days::post(day, solver, p)?;
+				println!("Day {day:>2} part {p}: accepted");
+
+				Ok(())
+			}
+
+			Command::Verify { day } => {
+
+				let mut failures = 0;
+
+				for days::Solution { day, part, solver, .. } in days::solutions().filter(|s| day.is_none_or(|d| d == s.day)) {
+					let verdict = days::verify(day, solver, part)?;
+					if matches!(verdict, Verdict::Fail { .. } | Verdict::Error(_)) { failures += 1 }
+					println!("Day {day:>2} part {part}: {verdict}");
+				}
+
+				if failures > 0 {
+					let msg = format!("{failures} solution(s) don't match the recorded answers");
+					return Err(AppError::IncorrectSolution(msg))
+				}
+
+				Ok(())
+			}
+
+			Command::Animate { day, part, input, delay } => {
+
+				let animation = days::animation(day).ok_or_else(|| {
+					AppError::MissingSolver(format!("Day {day} has no animation"))
+				})?;
+
+				let input = read_input(day, input.as_deref())?;
+
+				animation(&input, part, &mut |frame| {
+					// clear the screen, and draw from its top left corner
+					println!("\x1b[2J\x1b[H{frame}");
+					thread::sleep(delay);
+				})
+			}
+
+			Command::Bench { day, runs, save } => {
+
+				let mut baseline = Baseline::load()?;
+				let mut regressions = 0;
+
+				let by_day = days::solutions()
+					.filter(|s| day.is_none_or(|d| d == s.day))
+					.chunk_by(|s| s.day);
+
+				for (day,solutions) in &by_day {
+
+					let input = days::input_for(day)?;
+
+					for days::Solution { part, solver, .. } in solutions {
+						let measure = Measure::take(solver, &input, runs)?;
+						let verdict = match baseline.get(day, part) {
+							None => String::new(),
+							Some(previous) => match measure.regression(previous) {
+								None => "  ok".into(),
+								Some(regression) => {
+									regressions += 1;
+									format!("  REGRESSION ({regression})")
+								}
+							}
+						};
+						println!("Day {day:>2} part {part}: {measure}{verdict}");
+						if save { baseline.insert(day, part, measure) }
+					}
+				}
+
+				if save {
+					baseline.save()?;
+					println!("Baseline saved to {}", Baseline::PATH);
+				} else if regressions > 0 {
+					let msg = format!("{regressions} solution(s) got worse than the baseline");
+					return Err(AppError::Regression(msg))
+				}
+
+				Ok(())
+			}
+
+			Command::All => {
+
+				let by_day = days::solutions().chunk_by(|s| s.day);
+
+				for (day,solutions) in &by_day {
+
+					let input = days::input_for(day)?;
+
+					for days::Solution { part, solver, .. } in solutions {
+						println!("Day {day:>2} part {part}: {}", solver(&input)?);
+					}
+				}
+
+				Ok(())
+			}
+
+			Command::List => {
+
+				let mark = |done:bool| if done { "*" } else { "-" };
+
+				for (day,[p1,p2]) in days::implemented() {
+					println!("Day {day:>2}: {} {}", mark(p1), mark(p2));
+				}
+
+				Ok(())
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	fn args(line:&str) -> impl Iterator<Item=String> {
+		line.split_whitespace().map(String::from).collect_vec().into_iter()
+	}
+
+	#[test]
+	fn parse() {
+
+		let actual = Command::parse(args("run 3")).unwrap();
+		let expected = Command::Run { day: Day(3), part: None, input: None };
+		assert_eq!(actual, expected);
+
+		let actual = Command::parse(args("run 11 --input big.txt --part 2")).unwrap();
+		let expected = Command::Run { day: Day(11), part: Some(2), input: Some("big.txt".into()) };
+		assert_eq!(actual, expected);
+
+		let actual = Command::parse(args("submit 7 1")).unwrap();
+		let expected = Command::Submit { day: Day(7), part: 1 };
+		assert_eq!(actual, expected);
+
+		let actual = Command::parse(args("verify")).unwrap();
+		assert_eq!(actual, Command::Verify { day: None });
+
+		let actual = Command::parse(args("verify 5")).unwrap();
+		assert_eq!(actual, Command::Verify { day: Some(Day(5)) });
+
+		let actual = Command::parse(args("animate 6 --part 2 --delay 0")).unwrap();
+		let expected = Command::Animate { day: Day(6), part: 2, input: None, delay: Duration::ZERO };
+		assert_eq!(actual, expected);
+
+		let actual = Command::parse(args("bench")).unwrap();
+		assert_eq!(actual, Command::Bench { day: None, runs: 10, save: false });
+
+		let actual = Command::parse(args("bench 6 --save --runs 50")).unwrap();
+		assert_eq!(actual, Command::Bench { day: Some(Day(6)), runs: 50, save: true });
+
+		let actual = Command::parse(args("all")).unwrap();
+		assert_eq!(actual, Command::All);
+
+		let actual = Command::parse(args("list")).unwrap();
+		assert_eq!(actual, Command::List);
+
+		assert!(Command::parse(args("")).is_err());
+		assert!(Command::parse(args("run")).is_err());
+		assert!(Command::parse(args("run 26")).is_err());
+		assert!(Command::parse(args("run 1 --part 3")).is_err());
+		assert!(Command::parse(args("submit 1")).is_err());
+		assert!(Command::parse(args("all 1")).is_err());
+		assert!(Command::parse(args("animate 6 --delay soon")).is_err());
+		assert!(Command::parse(args("bench --runs 0")).is_err());
+		assert!(Command::parse(args("bench 1 2")).is_err());
+	}
+}
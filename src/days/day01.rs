@@ -1,60 +1,54 @@
 // https://adventofcode.com/2024/day/1
 
 use super::*;
+use crate::grammar::tokens;
 
 type Pair = (usize,usize);
 
-peg::parser!{
-
-	grammar line() for str {
-
-		rule _ = [' ' | '\t']
-		rule __ = _+
-
-		rule digit() -> char = [c if c.is_ascii_digit()]
-
-		rule id() -> usize
-			= n:$(digit()+) {? n.parse().or(Err("Expected usize value")) }
-
-		/// Matches a line from which two numbers separated
-		/// by whitespace can be extracted
-		pub rule pair() -> Pair
-			= lid:id() __ rid:id() { (lid,rid) }
-	}
+/// Matches a line from which two numbers separated
+/// by whitespace can be extracted
+fn pair(line:&str) -> Result<Pair,ParseError<LineCol>> {
+	tokens::pair(line, ' ')
 }
 
-fn solve_1(input: &str) -> String {
+/// Sum of the distances between the sorted lists
+fn distance(pairs:impl Iterator<Item=Result<Pair,AppError>>) -> Result<usize,AppError> {
 
-	let (mut left, mut right):(Vec<usize>,Vec<usize>) = Input(input).parse_iter(line::pair)
-		.unzip();
+	let (mut left, mut right):(Vec<usize>,Vec<usize>) = pairs
+		.process_results(|pairs| pairs.unzip())?;
 
 	left.sort();
 	right.sort();
 
-	let sum:usize = left.iter()
+	Ok(left.iter()
 		.zip(right)
 		.map(|(l,r)| l.abs_diff(r))
-		.sum();
-
-	sum.to_string()
+		.sum())
 }
 
-fn solve_2(input: &str) -> String {
+/// Sum of the left ids, each weighted by its count in the right list
+fn similarity(pairs:impl Iterator<Item=Result<Pair,AppError>>) -> Result<usize,AppError> {
 
-	let (left, right):(Vec<usize>,Vec<usize>) = Input(input).parse_iter(line::pair)
-		.unzip();
+	let (left, right):(Vec<usize>,Vec<usize>) = pairs
+		.process_results(|pairs| pairs.unzip())?;
 
-	let result:usize = left.iter()
+	Ok(left.iter()
 		.map(|l| l * right.iter().filter(|r| *r == l).count())
-		.sum();
+		.sum())
+}
 
-	result.to_string()
+pub fn solve_1(source:impl Source) -> Result<String,AppError> {
+	Ok(distance(source.parse_iter(pair))?.to_string())
 }
 
+pub fn solve_2(source:impl Source) -> Result<String,AppError> {
+	Ok(similarity(source.parse_iter(pair))?.to_string())
+}
+
+#[cfg(test)]
 mod test {
 
 	use super::*;
-	use aoc_driver::Part::*;
 
 	const EXAMPLE_INPUT:&str  =
 		r###"
@@ -70,7 +64,7 @@ mod test {
 	fn part_1_example() {
 
 		let expected : &str = "11";
-		let actual = solve_1(EXAMPLE_INPUT);
+		let actual = solve_1(&Input(&dedent(EXAMPLE_INPUT))).unwrap();
 		assert_eq!(actual, expected);
 	}
 
@@ -78,14 +72,28 @@ mod test {
 	fn part_2_example() {
 
 		let expected : &str = "31";
-		let actual = solve_2(EXAMPLE_INPUT);
+		let actual = solve_2(&Input(&dedent(EXAMPLE_INPUT))).unwrap();
 		assert_eq!(actual, expected);
 	}
 
+	#[test]
+	fn bad_input() {
+
+		const INPUT:&str = "3   4\n4   x\n";
+
+		let Err(AppError::BadInput(err)) = solve_1(&Input(INPUT)) else {
+			panic!("Input should be rejected")
+		};
+
+		assert_eq!((err.line, err.column), (2, 5));
+		assert_eq!(err.text, "4   x");
+	}
+
+
 	#[test]
 	fn submit()-> Result<(), AppError> {
-		try_submit(Day(1), solve_1, Part1)?;
-		try_submit(Day(1), solve_2, Part2)?;
+		try_submit(Day(1), |input| solve_1(&Input(input)), 1)?;
+		try_submit(Day(1), |input| solve_2(&Input(input)), 2)?;
 		Ok(())
 	}
 }
@@ -2,25 +2,13 @@
 
 use std::cmp::Ordering;
 use super::*;
+use crate::grammar::tokens;
 
 type Levels = Vec<usize>;
 
-peg::parser!{
-
-	grammar line() for str {
-		rule _ = [' ']
-		rule __ = _+
-
-		rule digit() -> char = [c if c.is_ascii_digit()]
-
-		rule level() -> usize
-			= n:$(digit()+) {? n.parse().or(Err("Expected usize value")) }
-
-		/// Matches a line with at least a pair of numbers,
-		/// separated by spaces
-		pub rule levels() -> Levels
-			= ( level() **<2,> _ )
-	}
+/// Matches a line with numbers separated by spaces
+fn levels(line:&str) -> Result<Levels,ParseError<LineCol>> {
+	tokens::list(line, ' ')
 }
 
 type Pair = (usize,usize);
@@ -72,20 +60,19 @@ pub fn is_safe<'a>(report: impl IntoIterator<Item=&'a usize> + 'a) -> bool {
 	}).is_some() // it folded
 }
 
-fn solve_1(input: &str) -> String {
-
-	let lines = Input(input).parse_iter(line::levels);
+/// Counts the safe reports
+fn count_safe(reports:impl Iterator<Item=Result<Levels,AppError>>) -> Result<usize,AppError> {
 
-	let safe_reports = lines.filter(|l| is_safe(l));
+	let safe_reports = reports.filter_ok(|l| is_safe(l));
 
-	safe_reports.count().to_string()
+	safe_reports.process_results(|reports| reports.count())
 }
 
-fn solve_2(input: &str) -> String {
+/// Counts the reports that are safe, or would
+/// be with a single level removed
+fn count_dampened(reports:impl Iterator<Item=Result<Levels,AppError>>) -> Result<usize,AppError> {
 
-	let lines = Input(input).parse_iter(line::levels);
-
-	let safe_reports = lines.filter(|full_report| {
+	let safe_reports = reports.filter_ok(|full_report| {
 
 		// try full report
 
@@ -93,7 +80,7 @@ fn solve_2(input: &str) -> String {
 
 			// try variations of the report, removing a single element
 
-			let enum_report = full_report.into_iter().enumerate();
+			let enum_report = full_report.iter().enumerate();
 
 			for i in 0..full_report.len() {
 
@@ -107,13 +94,21 @@ fn solve_2(input: &str) -> String {
 		}
 	});
 
-	safe_reports.count().to_string()
+	safe_reports.process_results(|reports| reports.count())
+}
+
+pub fn solve_1(source:impl Source) -> Result<String,AppError> {
+	Ok(count_safe(source.parse_iter(levels))?.to_string())
 }
 
+pub fn solve_2(source:impl Source) -> Result<String,AppError> {
+	Ok(count_dampened(source.parse_iter(levels))?.to_string())
+}
+
+#[cfg(test)]
 mod test {
 
 	use super::*;
-	use aoc_driver::Part::*;
 
 	const EXAMPLE_INPUT:&str  =
 		r###"
@@ -129,7 +124,7 @@ mod test {
 	fn part_1_example() {
 
 		let expected : &str = "2";
-		let actual = solve_1(EXAMPLE_INPUT);
+		let actual = solve_1(&Input(&dedent(EXAMPLE_INPUT))).unwrap();
 		assert_eq!(actual, expected);
 	}
 
@@ -137,14 +132,15 @@ mod test {
 	fn part_2_example() {
 
 		let expected : &str = "4";
-		let actual = solve_2(EXAMPLE_INPUT);
+		let actual = solve_2(&Input(&dedent(EXAMPLE_INPUT))).unwrap();
 		assert_eq!(actual, expected);
 	}
 
+
 	#[test]
 	fn submit()-> Result<(), AppError> {
-		try_submit(Day(2), solve_1, Part1)?;
-		try_submit(Day(2), solve_2, Part2)?;
+		try_submit(Day(2), |input| solve_1(&Input(input)), 1)?;
+		try_submit(Day(2), |input| solve_2(&Input(input)), 2)?;
 		Ok(())
 	}
 }
@@ -17,9 +17,9 @@ enum Chunk {
 	Noop
 }
 
-impl Into<Option<Op>> for Chunk {
-	fn into(self) -> Option<Op> {
-		match self {
+impl From<Chunk> for Option<Op> {
+	fn from(chunk: Chunk) -> Self {
+		match chunk {
 			Chunk::Op(op) => Some(op),
 			_ => None
 		}
@@ -32,6 +32,7 @@ peg::parser!{
 
 		rule digit() -> char = [c if c.is_ascii_digit()]
 
+		// the puzzle only allows 1 to 3 digits, unlike `tokens::unsigned`
 		rule number() -> usize
 			= n:$(digit()*<1,3>) {? n.parse().or(Err("Expected usize value")) }
 
@@ -64,7 +65,8 @@ fn ops_from(mut input: &str) -> impl Iterator<Item=Op> + use<> {
 
 	while !input.is_empty() {
 
-		let r = memory::next_op(input).unwrap();
+		// any char is consumed as a noop, so it can't fail
+		let r = memory::next_op(input).expect("A chunk should be parsed from non-empty input");
 
 		if let (Some(op),_) = r {
 			ops.push(op);
@@ -78,13 +80,12 @@ fn ops_from(mut input: &str) -> impl Iterator<Item=Op> + use<> {
 	ops.into_iter()
 }
 
-fn solve_1(input: &str) -> String {
+/// Sums the products of every multiplication
+fn sum_products(lines:impl Iterator<Item=Result<impl AsRef<str>,AppError>>) -> Result<usize,AppError> {
 
-	let lines = Input(input).lines();
+	lines.map_ok(|input| {
 
-	lines.map(|input| {
-
-		ops_from(input).flat_map(|op| {
+		ops_from(input.as_ref()).flat_map(|op| {
 			#[allow(unreachable_patterns)]
 			match op {
 				Op::Mul(a,b) => Some(a*b),
@@ -92,23 +93,22 @@ fn solve_1(input: &str) -> String {
 			}
 		}).sum::<usize>()
 
-
-	}).sum::<usize>().to_string()
+	}).sum()
 }
 
-fn solve_2(input: &str) -> String {
-
-	let lines = Input(input).lines();
+/// Sums the products of the multiplications
+/// that were allowed when reached
+fn sum_allowed_products(lines:impl Iterator<Item=Result<impl AsRef<str>,AppError>>) -> Result<usize,AppError> {
 
 	// mull allowed state must be carried over
 	// different lines
 	let mut mul_allowed = true;
 
-	lines.map(|input| {
+	lines.map_ok(|input| {
 
 		let mut sum:usize = 0;
 
-		for op in ops_from(input) {
+		for op in ops_from(input.as_ref()) {
 			match op {
 				Op::MulAllow(allow) => mul_allowed = allow,
 				Op::Mul(a,b) if mul_allowed => sum += a*b,
@@ -118,13 +118,21 @@ fn solve_2(input: &str) -> String {
 
 		sum
 
-	}).sum::<usize>().to_string()
+	}).sum()
+}
+
+pub fn solve_1(source:impl Source) -> Result<String,AppError> {
+	Ok(sum_products(source.lines())?.to_string())
+}
+
+pub fn solve_2(source:impl Source) -> Result<String,AppError> {
+	Ok(sum_allowed_products(source.lines())?.to_string())
 }
 
+#[cfg(test)]
 mod test {
 
 	use super::*;
-	use aoc_driver::Part::*;
 
 	#[test]
 	fn part_1_example() {
@@ -135,7 +143,7 @@ mod test {
 			"###;
 
 		let expected : &str = "161";
-		let actual:String = solve_1(INPUT);
+		let actual:String = solve_1(&Input(&dedent(INPUT))).unwrap();
 
 		assert_eq!(actual, expected);
 	}
@@ -149,15 +157,16 @@ mod test {
 			"###;
 
 		let expected : &str = "48";
-		let actual:String = solve_2(INPUT);
+		let actual:String = solve_2(&Input(&dedent(INPUT))).unwrap();
 
 		assert_eq!(actual, expected);
 	}
 
+
 	#[test]
 	fn submit()-> Result<(), AppError> {
-		try_submit(Day(3), solve_1, Part1)?;
-		try_submit(Day(3), solve_2, Part2)?;
+		try_submit(Day(3), |input| solve_1(&Input(input)), 1)?;
+		try_submit(Day(3), |input| solve_2(&Input(input)), 2)?;
 		Ok(())
 	}
 }
@@ -1,6 +1,6 @@
 // https://adventofcode.com/2024/day/4
 
-use std::iter::once;
+use std::convert::Infallible;
 use itertools::iproduct;
 
 use super::*;
@@ -8,224 +8,117 @@ use super::*;
 /// Represents a rectangle board of
 /// letters, stored as bytes.
 ///
-struct LetterBoard {
-	content: Vec<u8>,
-	size:(usize,usize),
+struct LetterBoard(Map);
+
+/// A word found on the board, spelled from `start` towards `heading`
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+struct Word<'a> {
+	text: &'a str,
+	start: Position,
+	heading: Compass,
 }
 
-impl LetterBoard {
+/// A rectangle of letters to look for, where
+/// `Pattern::WILDCARD` stands for any letter
+#[derive(Debug,Clone,PartialEq,Eq)]
+struct Pattern(Map);
 
-	fn horizontal<'a>(&'a self,fence:u8) -> impl Iterator<Item=u8> + 'a {
-		let ref content = self.content;
-		let (width,_) = self.size;
-		// add sentinel value at end of each row
-		content.chunks(width).map(move |chunk| chunk.iter().copied().chain(once(fence))).flatten()
-	}
+impl Pattern {
 
-	fn diagonal_1<'a>(&'a self, fence:u8) -> impl Iterator<Item=u8> + 'a {
-		let (width,height) = self.size;
-		let ref content = self.content;
-
-		//increased lengths to account for fences
-		let fwidth = width+1;
-		let fheight = height+1;
-
-		(0..fwidth*fheight).map(move |i| {
-			// increase each cycle
-			let col = i/fheight;
-			let x = (col + i%fheight)%fwidth;
-			let y = i%fheight; // downward
-
-			// fence bytes at right and bottom
-			if x == width || y == height {
-				fence
-			} else {
-				let offset = y*width+x;
-				content[offset]
-			}
-		})
-	}
+	const WILDCARD:u8 = b'.';
 
-	fn diagonal_2<'a>(&'a self,fence:u8) -> impl Iterator<Item=u8> + 'a {
-		let (width,height) = self.size;
-		let ref content = self.content;
-
-		//increased lengths to account for fences
-		let fwidth = width+1;
-		let fheight = height+1;
-
-		(0..fwidth*fheight).map(move |i| {
-			// increase each cycle
-			let col = i/fheight;
-			let x = (col + i%fheight)%fwidth;
-			let y = (fheight-1)-(i%fheight); // upward
-
-			// fence bytes at right and top
-			if x == width || y == 0 {
-				fence
-			} else {
-				// correct y to ignore top
-				let offset = (y-1)*width+x;
-				content[offset]
-			}
-		})
+	fn new(rows:&str) -> Result<Self,InputError> {
+		Input(rows).map(Ok::<u8,Infallible>).map(Self)
 	}
 
-	fn vertical<'a>(&'a self,fence:u8) -> impl Iterator<Item=u8> + 'a {
-
-		let (width,height) = self.size;
-		let ref content = self.content;
-
-		iproduct!(0..width,0..=height).map(move |(x,y)| {
-			if y == height {
-				fence
-			} else{
-				let offset = y*width+x;
-				content[offset]
-			}
-		})
+	/// The pattern turned a quarter clockwise
+	fn rotated(&self) -> Self {
+		let Field { width, height } = *self.0;
+		Self(Map::from_fn((height,width).into(), |p| self.0[Position{x:p.y,y:height-1-p.x}]))
 	}
 
-	fn chunks<'a>(&'a self,size:usize) -> Result<impl 'a + Iterator<Item=Vec<u8>>,String>  {
-
-		let (width,height) = self.size;
+	/// The pattern mirrored left to right
+	fn reflected(&self) -> Self {
+		let width = self.0.width;
+		Self(Map::from_fn(*self.0, |p| self.0[Position{x:width-1-p.x,y:p.y}]))
+	}
 
-		if width < size || height < size {
-			return Err(format!("Can't make chunks of {size} off a board of {width}x{height}"));
+	/// Every distinct way to lay the pattern on
+	/// a board, by rotating and reflecting it
+	fn variants(&self) -> Vec<Self> {
+		let rotations = |p:Self| std::iter::successors(Some(p), |p| Some(p.rotated())).take(4);
+		let mut variants:Vec<Self> = Vec::new();
+		for variant in rotations(self.clone()).chain(rotations(self.reflected())) {
+			if !variants.contains(&variant) { variants.push(variant) }
 		}
+		variants
+	}
 
-		let ref content = self.content;
-
-		// chunk origins
-		Ok(
-			(0..=width-size).cartesian_product(0..=height-size)
-				.map(move |(x,y)| {
-
-					// chunk rows
-					(y..y+size)
-						.map(move |y| {
-							let offset = y*width+x;
-							let ref row = content[offset..offset+size];
-							row.into_iter().copied()
-						})
-						.flatten()
-						.collect()
-				})
-		)
+	/// True when the pattern matches with its top left corner at `at`
+	fn matches(&self, board:&LetterBoard, at:Position) -> bool {
+		self.0.enumerate().all(|(p,letter)| {
+			letter == Self::WILDCARD ||
+			board.0.get(Position{x:at.x+p.x,y:at.y+p.y}) == Some(&letter)
+		})
 	}
 }
 
+impl LetterBoard {
 
-impl<'a> From<Input<'a>> for LetterBoard {
-
-	fn from(input:Input) -> Self {
-
-		let rows = input.lines().map(str::bytes);
-		// clone iterator, measure line width
-		let width = rows.clone().into_iter().take(1).flatten().count();
-
-		let content:Vec<u8> = rows.flatten().collect();
-
-		// compute height
-		let height = content.len() / width;
+	/// Every occurrence of the words, in any of the 8 directions
+	fn find_words<'a>(&'a self, words:&'a [&'a str]) -> impl Iterator<Item=Word<'a>> + 'a {
+		iproduct!(self.0.positions(), Compass::ALL, words)
+			.map(|(start,heading,&text)| Word { text, start, heading })
+			.filter(|word| self.0.line(word.start, word.heading.into()).take(word.text.len()).eq(word.text.bytes()))
+	}
 
-		Self { content, size: (width,height) }
+	/// Top left corners of the places where the pattern
+	/// matches, without rotating or reflecting it
+	fn find_pattern<'a>(&'a self, pattern:&'a Pattern) -> impl Iterator<Item=Position> + 'a {
+		let Field { width, height } = *self.0;
+		let xs = (width+1).saturating_sub(pattern.0.width);
+		let ys = (height+1).saturating_sub(pattern.0.height);
+		iproduct!(0..ys, 0..xs)
+			.map(|(y,x)| Position{x,y})
+			.filter(|&at| pattern.matches(self, at))
 	}
 }
 
-fn solve_1(input: &str) -> String {
-	//
-	fn count_matches(word:&[u8], input:&mut impl Iterator<Item=u8>) -> usize {
+impl<'a> TryFrom<Input<'a>> for LetterBoard {
 
-		let forward:Vec<u8>  = word.into();
-		let backward:Vec<u8> = forward.iter().rev().copied().collect();
+	type Error = InputError;
 
-		// load bytes of first possible match into a "buffer"
-		let mut window:Vec<u8> = input.take(word.len()).collect();
-		let mut count:usize = 0;
-
-		loop {
-
-			if window == forward || window == backward { count += 1 }
-
-			// fetch new byte
-			let buffer = &mut window;
-
-			let Some(c) = input.next() else {
-				// Done
-				break count
-			};
-
-			// update buffer
-			buffer.rotate_left(1);
-			if let Some(last) = buffer.last_mut() { *last = c };
-		}
+	fn try_from(input:Input) -> Result<Self,Self::Error> {
+		input.map(Ok::<u8,Infallible>).map(Self)
 	}
+}
 
-	let board = LetterBoard::from(Input(input));
-	let word = "XMAS";
+pub fn solve_1(input: &str) -> Result<String,AppError> {
 
-	let mut count = 0;
-	const FENCE:u8 = b'.';
-	count += count_matches(word.as_ref(), &mut board.horizontal(FENCE));
-	count += count_matches(word.as_ref(), &mut board.vertical(FENCE));
-	count += count_matches(word.as_ref(), &mut board.diagonal_1(FENCE));
-	count += count_matches(word.as_ref(), &mut board.diagonal_2(FENCE));
+	let board = LetterBoard::try_from(Input(input))?;
+	let count = board.find_words(&["XMAS"]).count();
 
-	count.to_string()
+	Ok(count.to_string())
 }
 
-fn solve_2(input: &str) -> String {
-
-	let board = LetterBoard::from(Input(input));
+pub fn solve_2(input: &str) -> Result<String,AppError> {
 
-	let chunks = board.chunks(3).unwrap();
+	let board = LetterBoard::try_from(Input(input))?;
+	let x_mas = Pattern::new("M.S\n.A.\nM.S")?;
 
-	let count = chunks
-		.filter(|c| {
-			// 'A' at the center
-			c[4] == b'A' &&
-			c[0] != c[8] &&
-			c[6] != c[2] &&
-			[c[0],c[2],c[6],c[8]].into_iter().all(|c| {
-				c == b'M' || c == b'S'
-			})
-		})
-		.count();
+	let count = x_mas.variants().iter()
+		.map(|variant| board.find_pattern(variant).count())
+		.sum::<usize>();
 
-	count.to_string()
+	Ok(count.to_string())
 }
 
 
 #[cfg(test)]
 mod test {
 
-	use aoc_driver::Part::*;
 	use super::*;
 
-	impl Display for LetterBoard {
-			fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-				write!(f, "{}", self.lines().format("\n"))
-			}
-	}
-
-	impl LetterBoard {
-
-		fn lines(&self) -> impl Iterator<Item=&str> {
-			use std::str::from_utf8;
-			self.content.iter().as_slice().chunks(self.size.0).map(|c| from_utf8(c).unwrap())
-		}
-
-		fn len(&self) -> usize {
-			self.content.len()
-		}
-	}
-
-	pub fn string<'a>(i:impl Iterator<Item=u8> + 'a) -> String {
-		use core::str::from_utf8;
-		from_utf8(i.collect::<Vec<_>>().as_slice()).unwrap().to_string()
-	}
-
 	const EXAMPLE_INPUT:&str =
 		r###"
 		MMMSXXMASM
@@ -250,45 +143,87 @@ mod test {
 			IJKL
 			"###;
 
-		let board = LetterBoard::from(Input(INPUT));
-		assert_eq!(board.len(),12);
+		let board = LetterBoard::try_from(Input(&dedent(INPUT))).unwrap();
+		assert_eq!(board.0.area(),12);
+		assert_grid_eq(&board.0, INPUT);
+
+		// words in every direction, including backwards and diagonally
+		let words = board.find_words(&["FGH","KF","CGK","D","XY"])
+			.map(|w| (w.text,w.start,w.heading))
+			.collect_vec();
+
+		assert_eq!(words, vec![
+			("CGK", Position{x:2,y:0}, Compass::South),
+			("D",   Position{x:3,y:0}, Compass::North),
+			("D",   Position{x:3,y:0}, Compass::NorthEast),
+			("D",   Position{x:3,y:0}, Compass::East),
+			("D",   Position{x:3,y:0}, Compass::SouthEast),
+			("D",   Position{x:3,y:0}, Compass::South),
+			("D",   Position{x:3,y:0}, Compass::SouthWest),
+			("D",   Position{x:3,y:0}, Compass::West),
+			("D",   Position{x:3,y:0}, Compass::NorthWest),
+			("FGH", Position{x:1,y:1}, Compass::East),
+			("KF",  Position{x:2,y:2}, Compass::NorthWest),
+		]);
+	}
 
-		// test horizontal
+	#[test]
+	fn patterns() {
 
-		// 5 rows of 6 items
-		let expected = "ABCD.EFGH.IJKL.";
-		let actual   = string(board.horizontal(b'.'));
-		assert_eq!(actual, expected);
+		let pattern = Pattern::new("AB\nC.").unwrap();
+		let variants = pattern.variants();
 
-		// test vertical
+		// every rotation and reflection is distinct
+		assert_eq!(variants.len(), 8);
+		assert_grid_eq(&variants[1].0, "CA\n.B");
+		assert_grid_eq(&variants[4].0, "BA\n.C");
 
-		// 6 columns of 5 items
-		let expected = "AEI.BFJ.CGK.DHL.";
-		let actual   = string(board.vertical(b'.'));
-		assert_eq!(actual, expected);
+		// symmetric patterns have fewer variants
+		assert_eq!(Pattern::new("M.S\n.A.\nM.S").unwrap().variants().len(), 4);
+		assert_eq!(Pattern::new("A.\n.A").unwrap().variants().len(), 2);
 
-		// test diagonals
+		let board = LetterBoard::try_from(Input("ABAB\nCDCX")).unwrap();
+		assert_eq!(board.find_pattern(&pattern).collect_vec(), vec![Position{x:0,y:0},Position{x:2,y:0}]);
 
-		// LTR "downward" strips
-		let expected = "AFK.BGL.CH..D.I..EJ.";
-		let actual   = string(board.diagonal_1(b'.'));
-		assert_eq!(actual, expected);
+		// patterns larger than the board never match
+		let large = Pattern::new("ABABA").unwrap();
+		assert_eq!(board.find_pattern(&large).count(), 0);
+	}
 
-		// LTR "upward" strips
-		let expected = "IFC.JGD.KH..L.A..EB.";
-		let actual   = string(board.diagonal_2(b'.'));
-		assert_eq!(actual, expected);
+	impl Word<'_> {
+
+		/// Positions of the letters, in reading order
+		fn positions(&self, board:&LetterBoard) -> impl Iterator<Item=Position> + use<> {
+			board.0.ray(self.start, self.heading.into()).take(self.text.len())
+		}
+	}
 
-		// 3x3 Chunks
-		let expected = vec!["ABCEFGIJK","BCDFGHJKL"];
-		let actual:Vec<String> = board.chunks(3).unwrap().map(|v| string(v.into_iter())).collect();
-		assert_eq!(actual,expected);
+	#[test]
+	fn xmas() {
+
+		let board = LetterBoard::try_from(Input(&dedent(EXAMPLE_INPUT))).unwrap();
+		let found = board.find_words(&["XMAS"])
+			.flat_map(|word| word.positions(&board))
+			.collect_vec();
+
+		assert_grid_eq(board.0.view(|_| '.').overlay(found.into_iter().map(|p| (p,char::from(board.0[p])))), "
+			....XXMAS.
+			.SAMXMS...
+			...S..A...
+			..A.A.MS.X
+			XMASAMX.MM
+			X.....XA.A
+			S.S.S.S.SS
+			.A.A.A.A.A
+			..M.M.M.MM
+			.X.X.XMASX
+		");
 	}
 
 	#[test]
 	fn part_1_example() {
 		let expected : &str = "18";
-		let actual:String = solve_1(EXAMPLE_INPUT);
+		let actual:String = solve_1(&dedent(EXAMPLE_INPUT)).unwrap();
 
 		assert_eq!(actual, expected);
 	}
@@ -296,7 +231,7 @@ mod test {
 	#[test]
 	fn part_2_example() {
 
-		let actual:String = solve_2(EXAMPLE_INPUT);
+		let actual:String = solve_2(&dedent(EXAMPLE_INPUT)).unwrap();
 		let expected : &str = "9";
 
 		assert_eq!(actual, expected);
@@ -304,8 +239,8 @@ mod test {
 
 	#[test]
 	fn submit()-> Result<(), AppError> {
-		try_submit(Day(4), solve_1, Part1)?;
-		try_submit(Day(4), solve_2, Part2)?;
+		try_submit(Day(4), solve_1, 1)?;
+		try_submit(Day(4), solve_2, 2)?;
 		Ok(())
 	}
 }
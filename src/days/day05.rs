@@ -1,200 +1,245 @@
 // https://adventofcode.com/2024/day/5
 
-use std::{cmp::Ordering, ops::{Deref, DerefMut}};
+use std::{collections::HashMap, ops::{Deref, DerefMut}};
 
 use super::*;
+use crate::grammar::tokens;
 
-#[derive(Debug,Clone,Copy)]
-struct Rule(u8,u8);
+type Page = usize;
 
-struct RuleSet(Vec<Rule>);
+#[derive(Debug,Clone,Copy)]
+struct Rule(Page,Page);
 
-impl From<Vec<Rule>> for RuleSet {
-	fn from(value: Vec<Rule>) -> Self {
-		RuleSet(value)
-	}
+/// Pages that should come before others, known from the
+/// rules as an adjacency matrix over every page they mention
+struct PageOrder {
+	index: HashMap<Page,usize>,
+	before: Vec<bool>,
 }
 
-impl Deref for RuleSet {
-	type Target = Vec<Rule>;
-	fn deref(&self) -> &Self::Target {
-		&self.0
+/// Pages whose rules each come before the next, and the last before the first
+#[derive(Debug,PartialEq)]
+struct Cycle(Vec<Page>);
+
+impl Display for Cycle {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let first = self.0.first().into_iter();
+		write!(f, "{}", self.0.iter().chain(first).join(" before "))
 	}
 }
 
-impl DerefMut for RuleSet {
-	fn deref_mut(&mut self) -> &mut Self::Target {
-		&mut self.0
-	}
+/// Why some pages can't be put in a single order
+#[derive(Debug,PartialEq)]
+enum Unsortable {
+	/// Their rules contradict each other
+	Cycle(Cycle),
+	/// Some pairs aren't ordered by any rule, even indirectly,
+	/// so several orders would be valid
+	Ambiguous(Vec<(Page,Page)>),
 }
 
-impl RuleSet {
-	fn applicable_to(&self, update:&Update) -> impl Iterator<Item=Rule> + Clone {
-		self.iter().copied().filter(|Rule(a,b)| {
-			update.contains(&a) && update.contains(&b)
-		})
+impl Display for Unsortable {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Unsortable::Cycle(cycle) => write!(f, "rules form a cycle, {cycle}"),
+			Unsortable::Ambiguous(pairs) => {
+				let pairs = pairs.iter().map(|(a,b)| format!("{a} and {b}")).join(", ");
+				write!(f, "several orders are valid, as no rule orders {pairs}")
+			}
+		}
 	}
 }
 
-#[derive(Debug)]
-struct Update(Vec<u8>);
+impl PageOrder {
 
-impl Deref for Update {
-	type Target = Vec<u8>;
-	fn deref(&self) -> &Self::Target {
-		&self.0
+	fn new(rules:&[Rule]) -> Self {
+
+		let index:HashMap<Page,usize> = rules.iter()
+			.flat_map(|&Rule(a,b)| [a,b])
+			.unique()
+			.enumerate()
+			.map(|(i,page)| (page,i))
+			.collect();
+
+		let n = index.len();
+		let mut before = vec![false; n*n];
+		for Rule(a,b) in rules {
+			before[index[a]*n + index[b]] = true;
+		}
+
+		Self { index, before }
 	}
-}
 
-impl DerefMut for Update {
-	fn deref_mut(&mut self) -> &mut Self::Target {
-		&mut self.0
+	/// True when a rule puts `a` before `b`
+	fn precedes(&self, a:Page, b:Page) -> bool {
+		match (self.index.get(&a), self.index.get(&b)) {
+			(Some(i),Some(j)) => self.before[i*self.index.len() + j],
+			_ => false
+		}
 	}
-}
 
-peg::parser!{
+	/// True when no rule puts a page after one that follows it
+	fn is_sorted(&self, pages:&[Page]) -> bool {
+		pages.iter().tuple_combinations().all(|(&a,&b)| !self.precedes(b,a))
+	}
 
-	grammar rules() for str {
+	/// Orders the pages so every rule between them holds, following
+	/// rules from page to page when there's none between two pages.
+	/// Fails when the rules form a cycle, or leave some pages unordered.
+	fn sort(&self, pages:&[Page]) -> Result<Vec<Page>,Unsortable> {
 
-		rule digit() -> char = [c if c.is_ascii_digit()]
+		let mut pending = pages.to_vec();
+		let mut sorted = Vec::with_capacity(pending.len());
+		let mut ambiguous = false;
 
-		rule number() -> u8
-			= n:$(digit()*<2>) {? n.parse().or(Err("Expected 2 digits usize value")) }
+		while !pending.is_empty() {
+			// pages no pending one has to precede
+			let ready = (0..pending.len())
+				.filter(|&i| !pending.iter().any(|&q| self.precedes(q,pending[i])))
+				.collect_vec();
 
-		pub rule pair() -> Rule
-			= a:number() "|" b:number() { Rule(a,b) }
-	}
-}
+			let Some(&first) = ready.first() else {
+				return Err(Unsortable::Cycle(self.cycle_among(&pending)))
+			};
+			ambiguous |= ready.len() > 1;
+			sorted.push(pending.remove(first));
+		}
 
-peg::parser!{
+		if ambiguous {
+			Err(Unsortable::Ambiguous(self.unordered(&sorted)))
+		} else {
+			Ok(sorted)
+		}
+	}
 
-	grammar update() for str {
+	/// Pairs of pages, already sorted, that no chain of rules orders
+	fn unordered(&self, sorted:&[Page]) -> Vec<(Page,Page)> {
 
-		rule digit() -> char = [c if c.is_ascii_digit()]
+		let n = sorted.len();
+		// whether a chain of rules leads from the i-th page to the j-th
+		let mut reach = vec![false; n*n];
 
-		rule number() -> u8
-			= n:$(digit()*<2>) {? n.parse().or(Err("Expected 2 digits usize value")) }
+		for j in 0..n {
+			for k in (0..j).filter(|&k| self.precedes(sorted[k],sorted[j])) {
+				reach[k*n + j] = true;
+				for i in 0..k {
+					reach[i*n + j] |= reach[i*n + k];
+				}
+			}
+		}
 
-		pub rule pages() -> Update
-			= p:(number() ** ",") { Update(p) }
+		(0..n).tuple_combinations()
+			.filter(|&(i,j)| !reach[i*n + j])
+			.map(|(i,j)| (sorted[i],sorted[j]))
+			.collect()
 	}
-}
 
-fn parse(input: &str)-> (RuleSet,impl Iterator<Item=Update>) {
+	/// Finds a cycle among pages that each have a predecessor
+	fn cycle_among(&self, pages:&[Page]) -> Cycle {
 
-	use core::iter::once;
+		// walking back from page to predecessor must come round
+		let mut path = vec![pages[0]];
 
-	// empty line separating rules from
-	// pages lists gets removed by lines()
-	let mut lines = Input(input).lines();
+		loop {
+			let last = path[path.len()-1];
+			let previous = pages.iter().copied().find(|&p| self.precedes(p,last)).unwrap();
 
-	let mut rules:RuleSet = RuleSet(vec![]);
+			if let Some(start) = path.iter().position(|&p| p == previous) {
+				let mut cycle = path[start..].iter().rev().copied().collect_vec();
+				// start with the lowest page, so a cycle reads the same
+				// whichever page it is found from
+				let lowest = cycle.iter().position_min().unwrap_or(0);
+				cycle.rotate_left(lowest);
+				return Cycle(cycle)
+			}
+			path.push(previous);
+		}
+	}
+}
 
-	let updates = loop {
+#[derive(Debug)]
+struct Update(Vec<Page>);
 
-		let line = lines.next()
-			.expect("Rules should precede start of update lines (which breaks the loop");
+impl Deref for Update {
+	type Target = Vec<Page>;
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
 
-		if let Ok(rule) = rules::pair(line) {
-			rules.push(rule);
-		} else {
-			// The first update line has been
-			// consumed, so must be reinserted
-			break once(line).chain(lines)
-				// create an iterator of Update
-				.map(|line| update::pages(line).unwrap());
-		}
+impl DerefMut for Update {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.0
+	}
+}
 
-	};
+impl Update {
+	fn middle(&self) -> usize {
+		self[(self.len()-1) / 2]
+	}
+}
 
-	(rules,updates)
+/// Matches a `47|53` rule line
+fn rule(line:&str) -> Result<Rule,ParseError<LineCol>> {
+	tokens::pair(line, '|').map(|(a,b)| Rule(a,b))
 }
 
-enum ValidatedUpdate {
-	Sorted(Update),
-	Unsorted(Update)
+/// Matches a `75,47,61` update line
+fn pages(line:&str) -> Result<Update,ParseError<LineCol>> {
+	tokens::list(line, ',').map(Update)
 }
 
-fn validate_updates(rules: &RuleSet, updates:impl Iterator<Item=Update>) -> impl Iterator<Item=ValidatedUpdate> {
-	updates
-		.map(|update| {
+fn parse(input: &str)-> Result<(PageOrder,impl Iterator<Item=Result<Update,AppError>>),AppError> {
 
-			let sorted = {
-				let rules = rules.applicable_to(&update);
-				update.is_sorted_by(|&a,&b|
-					rules.to_owned().any(|Rule(x,y)| x == a && y == b)
-				)
-			};
+	// rules, then updates, separated by a blank line
+	let input = Input(input);
+	let Some((rule_lines,update_lines)) = input.sections().into_iter().collect_tuple() else {
+		// nothing to point at, so blame the end of the input
+		let msg = "Rules should be followed by a blank line, and update lines";
+		return Err(input.last_line()?.error(0, msg).into())
+	};
 
-			if sorted {
-				ValidatedUpdate::Sorted(update)
-			} else {
-				ValidatedUpdate::Unsorted(update)
-			}
-		})
+	let rules:Vec<Rule> = rule_lines.parse_iter(rule).try_collect()?;
+	let updates = update_lines.parse_iter(pages);
+
+	Ok((PageOrder::new(&rules),updates))
 }
 
-fn solve_1(input: &str) -> String {
+pub fn solve_1(input: &str) -> Result<String,AppError> {
 
-	let (rules,updates) = parse(input);
+	let (order,updates) = parse(input)?;
 
-	validate_updates(&rules, updates)
-		.filter_map(|update|
-			match update {
-				ValidatedUpdate::Sorted(u) => Some(u),
-				_ => None
-			})
-		.map(|update| {
-			let middle = (update.len()-1) / 2;
-			update[middle] as usize
-		})
-		.sum::<usize>()
-		.to_string()
+	let sum = updates.process_results(|updates| {
+		updates
+			.filter(|update| order.is_sorted(update))
+			.map(|update| update.middle())
+			.sum::<usize>()
+	})?;
+
+	Ok(sum.to_string())
 }
 
-fn solve_2(input: &str) -> String {
+pub fn solve_2(input: &str) -> Result<String,AppError> {
 
-	let (rules,updates) = parse(input);
+	let (order,updates) = parse(input)?;
 
-	validate_updates(&rules, updates)
-		.filter_map(|update|
-			match update {
-				ValidatedUpdate::Unsorted(u) => Some(u),
-				_ => None
-			}
-		)
-		.inspect(|u| println!("{:?}",u))
-		.map(|mut update| {
-
-			let rules:RuleSet = rules.applicable_to(&update).collect::<Vec<Rule>>().into();
-
-			update.sort_by(|a,b| {
-
-				rules.iter().find_map(|Rule(x,y)|
-					if x == a && y == b {
-						Some(Ordering::Less)
-					} else if x == b && y == a {
-						Some(Ordering::Greater)
-					} else {
-						None
-					}
-				).or_else(|| {
-					panic!("No rule found for pair {a},{b}!");
-				}).unwrap()
-
-			});
-
-			let middle = (update.len()-1) / 2;
-			update[middle] as usize
+	let sum = updates
+		.filter_ok(|update| !order.is_sorted(update))
+		.map(|update| {
+			let update = update?;
+			let sorted = order.sort(&update)
+				.map_err(|reason| AppError::Unsolvable(format!("Can't sort update {:?}, {reason}", update.0)))?;
+			Ok::<_,AppError>(Update(sorted).middle())
 		})
-		.sum::<usize>()
-		.to_string()
+		.sum::<Result<usize,AppError>>()?;
+
+	Ok(sum.to_string())
 }
 
+#[cfg(test)]
 mod test {
 
 	use super::*;
-	use aoc_driver::Part::*;
 
 	const INPUT_EXAMPLE:&str =
 		r###"
@@ -228,11 +273,62 @@ mod test {
 		97,13,75,29,47
 		"###;
 
+	#[test]
+	fn page_order() {
+
+		let (order,_) = parse(&dedent(INPUT_EXAMPLE)).unwrap();
+
+		assert!(order.precedes(47,53));
+		assert!(!order.precedes(53,47));
+		assert!(!order.precedes(47,100));
+
+		assert!(order.is_sorted(&[75,47,61,53,29]));
+		assert!(!order.is_sorted(&[97,13,75,29,47]));
+		assert_eq!(order.sort(&[97,13,75,29,47]), Ok(vec![97,75,47,29,13]));
+		assert_eq!(order.unordered(&[97,75,47,29,13]), vec![]);
+		assert_eq!(order.sort(&[13,29,47,53,61,75,97]), Ok(vec![97,75,47,61,53,29,13]));
+
+		// pages of any width, in a cycle
+		let order = PageOrder::new(&[Rule(7,123),Rule(123,4567),Rule(4567,7),Rule(1,7)]);
+		let Err(Unsortable::Cycle(cycle)) = order.sort(&[1,123,7,4567]) else { panic!("Expected a cycle") };
+		assert_eq!(cycle.to_string(), "7 before 123 before 4567 before 7");
+		// ...reading the same whichever page it's found from
+		assert_eq!(order.sort(&[4567,7,123,1]), Err(Unsortable::Cycle(cycle)));
+
+		// ...which doesn't matter to updates leaving it out
+		assert_eq!(order.sort(&[123,1,7]), Ok(vec![1,7,123]));
+	}
+
+	#[test]
+	fn incomplete_rules() {
+
+		// no rule between 1 and 3, but 2 is in between
+		let order = PageOrder::new(&[Rule(1,2),Rule(2,3)]);
+		assert_eq!(order.sort(&[3,1,2]), Ok(vec![1,2,3]));
+
+		// nothing orders 2 and 3, nor 4 and 5
+		let order = PageOrder::new(&[Rule(1,2),Rule(1,3),Rule(3,4)]);
+		let actual = order.sort(&[5,4,3,2,1]);
+		let expected = Err(Unsortable::Ambiguous(vec![(5,1),(5,3),(5,4),(5,2),(3,2),(4,2)]));
+		assert_eq!(actual, expected);
+
+		let input = "1|2\n1|3\n\n3,2,1";
+		let actual = solve_2(input).unwrap_err().to_string();
+		let expected = "Unsolvable input: Can't sort update [3, 2, 1], several orders are valid, as no rule orders 3 and 2";
+		assert_eq!(actual, expected);
+
+		// a cycle in the rules only matters to updates with every page in it
+		let input = "1|2\n2|3\n3|1\n\n2,1\n3,2,1";
+		let actual = solve_2(input).unwrap_err().to_string();
+		let expected = "Unsolvable input: Can't sort update [3, 2, 1], rules form a cycle, 1 before 2 before 3 before 1";
+		assert_eq!(actual, expected);
+	}
+
 	#[test]
 	fn part_1_example() {
 
 		let expected : &str = "143";
-		let actual:String = solve_1(INPUT_EXAMPLE);
+		let actual:String = solve_1(&dedent(INPUT_EXAMPLE)).unwrap();
 
 		assert_eq!(actual, expected);
 	}
@@ -241,15 +337,26 @@ mod test {
 	fn part_2_example() {
 
 		let expected : &str = "123";
-		let actual:String = solve_2(INPUT_EXAMPLE);
+		let actual:String = solve_2(&dedent(INPUT_EXAMPLE)).unwrap();
 
 		assert_eq!(actual, expected);
 	}
 
+	#[test]
+	fn bad_input() {
+
+		let Err(AppError::BadInput(err)) = solve_1("1|2\n1,2\n\n\n") else { panic!("Expected an input error") };
+		assert_eq!((err.line,err.text.as_str()), (2,"1,2"));
+
+		// not mistaken for the first update
+		let Err(AppError::BadInput(err)) = solve_1("1|2\n1-3\n\n1,2") else { panic!("Expected an input error") };
+		assert_eq!((err.line,err.column), (2,2));
+	}
+
 	#[test]
 	fn submit()-> Result<(), AppError> {
-		try_submit(Day(5), solve_1, Part1)?;
-		try_submit(Day(5), solve_2, Part2)?;
+		try_submit(Day(5), solve_1, 1)?;
+		try_submit(Day(5), solve_2, 2)?;
 		Ok(())
 	}
 
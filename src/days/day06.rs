@@ -1,8 +1,32 @@
 // https://adventofcode.com/2024/day/6
 
-use std::iter::once;
+use std::{collections::BTreeSet, iter::once};
 use super::*;
 
+peg::parser!{
+
+	grammar settings() for str {
+
+		rule _ = [' ' | '\t']*
+
+		rule comment() = "#" [_]*
+
+		rule orientation() -> Direction
+			= c:['N' | 'E' | 'S' | 'W'] {? Direction::try_from(c).or(Err("Expected orientation")) }
+
+		rule protocol() -> guard::Protocol
+			= "right" { guard::Protocol::turn_right() }
+			/ "left" { guard::Protocol::turn_left() }
+			/ "around" { guard::Protocol::u_turn() }
+			/ o:orientation() ++ _ {? guard::Protocol::sequence(o).ok_or("Expected orientations") }
+
+		/// Matches a `turn = protocol` line, or a comment line
+		pub rule line() -> Option<guard::Protocol>
+			= _ "turn" _ "=" _ p:protocol() _ comment()? { Some(p) }
+			/ _ comment()? { None }
+	}
+}
+
 mod guard {
 
 	use super::*;
@@ -11,20 +35,90 @@ mod guard {
 	pub struct State {
 		pub location: Position,
 		pub orientation: Direction,
+		/// How far along its protocol the guard is
+		pub turn: usize,
+	}
+
+	/// Orientations the guard takes in turn when finding an obstacle,
+	/// cycling through them. The first one is the initial orientation.
+	///
+	/// Turning relative to the current orientation is just a fixed
+	/// sequence too, as the guard always starts facing north.
+	#[derive(Debug,Clone,PartialEq,Eq)]
+	pub struct Protocol(Vec<Direction>);
+
+	impl Default for Protocol {
+		fn default() -> Self {
+			Self::turn_right()
+		}
+	}
+
+	impl Protocol {
+
+		/// Turning the same way every time, until facing north again
+		fn turning(turn:fn(Direction) -> Direction) -> Self {
+			let orientations = std::iter::successors(Some(Direction::North), |&d| Some(turn(d)));
+			Self(orientations.take_while_inclusive(|&d| turn(d) != Direction::North).collect())
+		}
+
+		/// Turning 90 degrees right (the puzzle rule)
+		pub fn turn_right() -> Self {
+			Self::turning(Direction::turn_right)
+		}
+
+		pub fn turn_left() -> Self {
+			Self::turning(Direction::turn_left)
+		}
+
+		pub fn u_turn() -> Self {
+			Self::turning(Direction::opposite)
+		}
+
+		/// Any sequence of orientations, as long as it's not empty
+		pub fn sequence(orientations:impl IntoIterator<Item=Direction>) -> Option<Self> {
+			let orientations = orientations.into_iter().collect_vec();
+			if orientations.is_empty() { None } else { Some(Self(orientations)) }
+		}
+
+		/// Reads the protocol from lines such as:
+		///
+		/// ```text
+		/// # the guard turns right unless told otherwise
+		/// turn = left   # or `right`, `around`, or orientations like `N E N W`
+		/// ```
+		///
+		/// When given more than once, the last one wins.
+		pub fn read<'a>(lines:impl Iterator<Item=Line<'a>>) -> Result<Self,InputError> {
+			lines
+				.map(|line| line.parse(settings::line))
+				.process_results(|protocols| protocols.flatten().last().unwrap_or_default())
+		}
+
+		/// Amount of turns until the protocol repeats
+		pub fn period(&self) -> usize {
+			self.0.len()
+		}
+
+		pub fn start(&self, location:Position) -> State {
+			State { location, orientation: self.0[0], turn: 0 }
+		}
+
+		/// The state after turning once, in place
+		pub fn turn(&self, state:State) -> State {
+			let turn = (state.turn + 1) % self.period();
+			State { orientation: self.0[turn], turn, ..state }
+		}
 	}
 
 	pub struct Route<'a> {
 		pub room: &'a Map,
 		pub state: State,
-		pub protocol: Box<dyn Iterator<Item=Direction>>
+		pub protocol: &'a Protocol,
 	}
 
 	impl<'a> Route<'a> {
-		pub fn new(room:&'a Map,start:Position) -> Self {
-			use Direction::*;
-			let mut protocol = Box::new([North,East,South,West].into_iter().cycle());
-			let orientation = protocol.next().unwrap();
-			let state = State { location: start, orientation };
+		pub fn new(room:&'a Map,start:Position,protocol:&'a Protocol) -> Self {
+			let state = protocol.start(start);
 			Route { room, protocol, state }
 		}
 	}
@@ -34,33 +128,11 @@ mod guard {
 
 		fn next(&mut self) -> Option<Self::Item> {
 
-			use Direction::*;
-
-			let State { location: Position{x,y}, orientation } = self.state;
-
-			// check exiting room condition
-
-			let guard_exits = {
-
-				let min = Position::zero();
-				let max = self.room.last_position();
-
-				orientation == North && y == min.y ||
-				orientation == East  && x == max.x ||
-				orientation == South && y == max.y ||
-				orientation == West  && x == min.x
-			};
-
-			if guard_exits { None } else {
+			let State { location, orientation, .. } = self.state;
 
-				// tries to take a step ahead
+			// tries to take a step ahead, unless exiting the room
 
-				let position_ahead = match orientation {
-					North => Position{x,y:y-1},
-					East  => Position{x:x+1,y},
-					South => Position{x,y:y+1},
-					West  => Position{x:x-1,y},
-				};
+			self.room.step(location, orientation.into()).map(|position_ahead| {
 
 				let obstructed = self.room[position_ahead] == b'#';
 
@@ -71,18 +143,141 @@ mod guard {
 					//
 					// NOTICE: this means that we will return repeated
 					// consecutive positions every turn
-					self.state.orientation = self.protocol.next().unwrap();
+					self.state = self.protocol.turn(self.state);
 				}
 
-				Some(self.state.to_owned())
+				self.state.to_owned()
+			})
+		}
+	}
+}
+
+/// Faster loop detection, moving the guard from obstacle to obstacle,
+/// rather than cell by cell
+mod jumps {
+
+	use super::*;
+	use guard::{Protocol, State};
+
+	/// Index of a state into tables holding a value per location and orientation
+	fn index(field:&Field, State { location, orientation, .. }:State) -> usize {
+		field.offset_of(location).unwrap() * 4 + orientation as usize
+	}
+
+	/// Set of guard states, one bit each
+	pub struct Visited {
+		field: Field,
+		period: usize,
+		bits: Vec<u64>,
+	}
+
+	impl Visited {
+
+		/// Room for the states of a guard following a protocol,
+		/// which are told apart by their turn, not orientation
+		pub fn new(field:&Field, protocol:&Protocol) -> Self {
+			let period = protocol.period();
+			let states = field.area() * period;
+			Self { field: *field, period, bits: vec![0; states.div_ceil(64)] }
+		}
+
+		/// Adds a state, returning false when it was already there
+		fn insert(&mut self, state:State) -> bool {
+			let i = self.field.offset_of(state.location).unwrap() * self.period + state.turn;
+			let (word,bit) = (i/64, 1 << (i%64));
+			let new = self.bits[word] & bit == 0;
+			self.bits[word] |= bit;
+			new
+		}
+
+		fn clear(&mut self) {
+			self.bits.fill(0);
+		}
+	}
+
+	/// Where the guard stops walking straight ahead from every state:
+	/// in front of the next obstacle, or `None` when leaving the room
+	pub struct Jumps {
+		field: Field,
+		stops: Vec<Option<Position>>,
+	}
+
+	impl Jumps {
+
+		pub fn new(room:&Map) -> Self {
+
+			use Direction::*;
+
+			let field:Field = **room;
+			let mut stops = vec![None; field.area() * 4];
+			let positions = field.positions().collect_vec();
+
+			for orientation in Direction::ALL {
+
+				// Visit the positions so that the one ahead is always resolved first
+				let order:Box<dyn Iterator<Item=&Position>> = match orientation {
+					North | West => Box::new(positions.iter()),
+					East | South => Box::new(positions.iter().rev()),
+				};
+
+				for &location in order {
+					let stop = match field.step(location, orientation.into()) {
+						None => None,
+						Some(ahead) if room[ahead] == b'#' => Some(location),
+						Some(ahead) => stops[index(&field, State { location: ahead, orientation, turn: 0 })],
+					};
+					stops[index(&field, State { location, orientation, turn: 0 })] = stop;
+				}
+			}
+
+			Self { field, stops }
+		}
+
+		/// Where the guard stops, with an extra obstacle in the room
+		fn stop(&self, state:State, obstacle:Position) -> Option<Position> {
+
+			use Direction::*;
+
+			let State { location: p, orientation, .. } = state;
+			let stop = self.stops[index(&self.field, state)];
+			let o = obstacle;
+
+			// The extra obstacle is hit when it's in the way, before the stop
+			let hit = match orientation {
+				North => o.x == p.x && o.y < p.y && stop.is_none_or(|s| o.y >= s.y),
+				East  => o.y == p.y && o.x > p.x && stop.is_none_or(|s| o.x <= s.x),
+				South => o.x == p.x && o.y > p.y && stop.is_none_or(|s| o.y <= s.y),
+				West  => o.y == p.y && o.x < p.x && stop.is_none_or(|s| o.x >= s.x),
+			};
+
+			if hit { self.field.step(o, orientation.opposite().into()) } else { stop }
+		}
+
+		/// True when the guard, starting at some state, never
+		/// leaves the room after placing an extra obstacle
+		pub fn loops(&self, start:State, protocol:&Protocol, obstacle:Position, visited:&mut Visited) -> bool {
+
+			visited.clear();
+
+			let mut state = start;
+
+			// Loops are detected by the states right after turning,
+			// as any loop has to go through them
+			while let Some(location) = self.stop(state, obstacle) {
+				state = protocol.turn(State { location, ..state });
+				if !visited.insert(state) { return true }
 			}
+
+			false
 		}
 	}
 }
 
+#[derive(Debug)]
 struct Simulation {
 	room: Map,
-	start: Position
+	start: Position,
+	protocol: guard::Protocol,
 }
 
 impl Simulation {
@@ -95,27 +290,80 @@ impl Simulation {
 		self.room[p] = b'.';
 	}
 
-	pub fn iter(&self) -> impl Iterator<Item=guard::State> {
-		self.into_iter()
-			// Removes states that do not produce advance (rotating in place)
-			// NOTICE: Luckily for us, `dedup_by` seems to keep
-			// the last value, not the first.
-			.dedup_by(|a,b| a.location == b.location)
+	/// States of the guard until leaving the room,
+	/// or until starting over when it loops
+	pub fn patrol(&self) -> impl Iterator<Item=guard::State> {
+		let mut visited = BTreeSet::new();
+		self.into_iter().take_while(move |&step| visited.insert(step))
+	}
+
+	/// Unique locations along the guard trail
+	pub fn trail(&self) -> impl Iterator<Item=Position> + use<> {
+		let mut locations = self.patrol().map(|s| s.location).collect_vec();
+		locations.sort();
+		locations.into_iter().dedup()
+	}
+
+	/// True when the guard never leaves the room
+	pub fn loops(&self) -> bool {
+		// A loop is detected when the guard re-visit
+		// a location at the same point of its protocol
+		let mut visited = BTreeSet::new();
+		self.into_iter().any(|step| !visited.insert(step))
+	}
+
+	/// Draws the room at every step of the guard route, with the cells
+	/// visited so far marked with `X`, and an arrow for the guard.
+	/// The route ends when the guard exits the room, or loops.
+	pub fn replay(&self, obstacle:Option<Position>, show:&mut dyn FnMut(String)) {
+
+		let mut visited = BTreeSet::new();
+		let mut trail = Vec::new();
+
+		for (step,state) in self.into_iter().enumerate() {
+
+			trail.push(state.location);
+			let looped = !visited.insert(state);
+
+			let mut status = format!("step {step}");
+			if let Some(o) = obstacle { status += &format!(", obstacle at {o}") }
+			if looped { status += ", loop detected" }
+
+			let room = self.room.view(char::from)
+				.highlight(trail.iter().copied(), 'X')
+				.highlight(obstacle, 'O')
+				.overlay([(state.location, char::from(state.orientation))]);
+
+			show(format!("{room}\n{status}"));
+
+			if looped { break }
+		}
 	}
 }
 
-impl<'a, L:Iterator<Item=&'a str>+Clone> From<L> for Simulation {
+impl Simulation {
 
-	fn from(lines:L)-> Self {
-		let room = Map::from(lines);
-		let start = {
-			let offset = room.iter().position(|item| item == b'^')
-				.expect("There room data should have a '^' character somewhere");
+	/// Reads the room, optionally followed by the guard
+	/// protocol after a blank line, see `Protocol::read`
+	fn read(input:Input)-> Result<Self,InputError> {
 
-			room.position_of(offset).unwrap()
+		let sections = input.sections();
+		let Some((room_lines,settings)) = sections.split_first() else {
+			return Err(InputError::new(1, 1, "", "Input is empty"))
 		};
 
-		Self { room, start }
+		let (room,markers) = room_lines.marked_map(b"^", |b| match b {
+			b'.' | b'#' | b'^' => Ok(b),
+			_ => Err(format!("Expected '.', '#' or '^', found '{}'", b as char))
+		})?;
+		let Some(start) = markers.get(b'^') else {
+			// nothing to point at, so blame the end of the room
+			return Err(room_lines.last_line().error(0, "The room should have a '^' character somewhere"))
+		};
+
+		let protocol = guard::Protocol::read(settings.iter().flat_map(Section::numbered_lines))?;
+
+		Ok(Self { room, start, protocol })
 	}
 }
 
@@ -124,72 +372,84 @@ impl<'a> IntoIterator for &'a Simulation {
 	type IntoIter = Box<dyn Iterator<Item=guard::State> + 'a>;
 
 	fn into_iter(self) -> Self::IntoIter {
-		let route = guard::Route::new(&self.room,self.start);
+		let route = guard::Route::new(&self.room,self.start,&self.protocol);
 		Box::new(once(route.state).chain(route))
 	}
 }
 
-fn solve_1(input: &str) -> String {
+/// Amount of locations the guard goes through
+fn visited(simulation:&Simulation) -> usize {
+	simulation.trail().count()
+}
 
-	let simulation = Simulation::from(Input(input).lines());
+/// Amount of locations where an obstacle makes the guard loop
+fn obstructions(simulation:&Simulation) -> usize {
 
-	let mut offsets:Vec<usize> = simulation.iter()
-		.map(|guard::State{location,..}| simulation.room.offset_of(location).unwrap())
-		.collect();
+	let room = &simulation.room;
+	let protocol = &simulation.protocol;
 
-	offsets.sort();
-	offsets.iter()
-		// remove repeated positions due to paths crossing
-		.dedup()
-		.count()
-		.to_string()
-}
+	let jumps = jumps::Jumps::new(room);
+	let mut visited = jumps::Visited::new(room, protocol);
+
+	// Locations the guard already went through, where placing an
+	// obstacle would have changed the route earlier
+	let mut entered = vec![false; room.area()];
+	entered[room.offset_of(simulation.start).unwrap()] = true;
 
-fn solve_2(input: &str) -> String {
+	simulation.patrol()
+		.tuple_windows()
+		.filter(|(before,after)| {
 
-	use std::collections::BTreeSet;
+			// Turning in place
+			if before.location == after.location { return false }
 
-	let mut simulation = Simulation::from(Input(input).lines());
+			let first_time = !std::mem::replace(&mut entered[room.offset_of(after.location).unwrap()], true);
 
-	let mut guard_path = simulation.iter().map(|s| s.location).collect_vec();
+			// Put an obstacle where the guard would go, and resume from there
+			first_time && jumps.loops(*before, protocol, after.location, &mut visited)
+		})
+		.count()
+}
 
-	// Try only unique locations along the guard trail
-	guard_path.sort();
-	guard_path.into_iter().dedup()
-		.filter(|&location| {
+pub fn solve_1(input: &str) -> Result<String,AppError> {
+	let simulation = Simulation::read(Input(input))?;
+	Ok(visited(&simulation).to_string())
+}
 
-			// An obstruction cannot be placed at the start position
-			if location == simulation.start { return false }
+pub fn solve_2(input: &str) -> Result<String,AppError> {
+	let simulation = Simulation::read(Input(input))?;
+	Ok(obstructions(&simulation).to_string())
+}
 
-			let mut it_loops:bool = false;
-			let mut visited = BTreeSet::new();
+/// Replays the guard route. For part 2, the route is replayed
+/// for every obstacle that makes the guard loop.
+pub fn animate(input: &str, part:u8, show:&mut dyn FnMut(String)) -> Result<(),AppError> {
 
-			simulation.ins_obstacle(location);
+	let mut simulation = Simulation::read(Input(input))?;
 
-			for step in simulation.iter() {
+	if part == 1 {
+		simulation.replay(None, show);
+		return Ok(())
+	}
 
-				// A loop is detected when the guard re-visit
-				// a location facing at the same direction
+	for location in simulation.trail() {
 
-				if !visited.insert(step) {
-					it_loops = true;
-					break
-				}
-			}
+		if location == simulation.start { continue }
 
-			simulation.del_obstacle(location);
+		simulation.ins_obstacle(location);
+		if simulation.loops() {
+			simulation.replay(Some(location), show);
+		}
+		simulation.del_obstacle(location);
+	}
 
-			return it_loops
-		})
-		.count()
-		.to_string()
+	Ok(())
 }
 
 #[cfg(test)]
 mod test {
 
 	use super::*;
-	use aoc_driver::Part::*;
 
 	const INPUT_EXAMPLE:&str =
 		r###"
@@ -205,28 +465,185 @@ mod test {
 		......#...
 		"###;
 
+	/// Reference way of finding obstructions, simulating
+	/// the whole route for every obstacle
+	fn obstructions_simulated(simulation:&mut Simulation) -> usize {
+
+		// Try only unique locations along the guard trail
+		simulation.trail()
+			.filter(|&location| {
+
+				// An obstruction cannot be placed at the start position
+				if location == simulation.start { return false }
+
+				simulation.ins_obstacle(location);
+				let it_loops = simulation.loops();
+				simulation.del_obstacle(location);
+
+				it_loops
+			})
+			.count()
+	}
+
 	#[test]
 	fn part_1_example() {
 
 		let expected = "41";
-		let actual = solve_1(INPUT_EXAMPLE);
+		let actual = solve_1(&dedent(INPUT_EXAMPLE)).unwrap();
 
 		assert_eq!(actual,expected);
 	}
 
+	#[test]
+	fn patrol() {
+
+		let simulation = Simulation::read(Input(&dedent(INPUT_EXAMPLE))).unwrap();
+		let view = simulation.room.view(char::from).highlight(simulation.trail(), 'X');
+
+		assert_grid_eq(view, "
+			....#.....
+			....XXXXX#
+			....X...X.
+			..#.X...X.
+			..XXXXX#X.
+			..X.X.X.X.
+			.#XXXXXXX.
+			.XXXXXXX#.
+			#XXXXXXX..
+			......#X..
+		");
+	}
+
 	#[test]
 	fn part_2_example() {
 
 		let expected = "6";
-		let actual = solve_2(INPUT_EXAMPLE);
+		let actual = solve_2(&dedent(INPUT_EXAMPLE)).unwrap();
 
 		assert_eq!(actual,expected);
 	}
 
+	#[test]
+	fn jumps() {
+
+		// Jumping from obstacle to obstacle finds the same loops as
+		// walking the whole route, see `timings` for how long each takes
+		let mut s = Simulation::read(Input(&dedent(INPUT_EXAMPLE))).unwrap();
+		assert_eq!(obstructions(&s), 6);
+		assert_eq!(obstructions_simulated(&mut s), 6);
+
+		// ...and for a guard leaving the room after a single turn
+		let mut s = Simulation::read(Input("...#\n#..^\n..#.")).unwrap();
+		assert_eq!(obstructions(&s), obstructions_simulated(&mut s));
+	}
+
+	/// Times both ways of finding obstructions on the puzzle input, with
+	/// `cargo test timings -- --ignored --nocapture`
+	#[test]
+	#[ignore = "prints timings, needs inputs/6.txt"]
+	fn timings() {
+
+		use crate::bench::Measure;
+
+		fn solve_2_simulated(input: &str) -> Result<String,AppError> {
+			let mut simulation = Simulation::read(Input(input))?;
+			Ok(obstructions_simulated(&mut simulation).to_string())
+		}
+
+		let input = std::fs::read_to_string("inputs/6.txt").expect("Puzzle input should be in inputs/6.txt");
+		let jumping = Measure::take(solve_2, &input, 3).unwrap();
+		let simulated = Measure::take(solve_2_simulated, &input, 3).unwrap();
+		assert_eq!(solve_2(&input).unwrap(), solve_2_simulated(&input).unwrap());
+
+		println!("Day 6 part 2, jumping:   {jumping}");
+		println!("Day 6 part 2, simulated: {simulated}");
+	}
+
+	#[test]
+	fn bad_input() {
+
+		let err = Simulation::read(Input(&dedent(INPUT_EXAMPLE).replacen("#.....", "#..x..", 1))).unwrap_err();
+		assert_eq!((err.line,err.column), (2,8));
+
+		let err = Simulation::read(Input(&dedent(INPUT_EXAMPLE).replace('^', "."))).unwrap_err();
+		assert!(err.reason.contains("'^'"));
+		assert_eq!((err.line,err.text.as_str()), (11,"......#..."));
+	}
+
+	#[test]
+	fn protocols() {
+
+		use guard::Protocol;
+		use Direction::*;
+
+		let simulation = |settings:&str| {
+			Simulation::read(Input(&(dedent(INPUT_EXAMPLE) + "\n" + settings))).unwrap()
+		};
+
+		// The puzzle rule, spelled out
+		let mut s = simulation("turn = N E S W");
+		assert_eq!(s.protocol, Protocol::sequence([North,East,South,West]).unwrap());
+		assert_eq!(visited(&s), 41);
+		assert_eq!(obstructions(&s), 6);
+		assert_eq!(obstructions_simulated(&mut s), 6);
+
+		// Turning left, the guard leaves through the west wall
+		let mut s = simulation("# the other way\nturn = left");
+		assert_eq!(visited(&s), 10);
+		assert_eq!(obstructions(&s), obstructions_simulated(&mut s));
+
+		// Turning back, the guard leaves through the south wall
+		let mut s = simulation("turn = right\nturn = around  # last one wins");
+		assert_eq!(s.protocol, Protocol::u_turn());
+		assert_eq!(visited(&s), 9);
+		assert_eq!(obstructions(&s), 3);
+		assert_eq!(obstructions_simulated(&mut s), 3);
+
+		// ...unless there's an obstacle on the way
+		let s = Simulation::read(Input("#\n.\n^\n#\n\nturn = around")).unwrap();
+		assert!(s.loops());
+		assert_eq!(visited(&s), 2);
+
+		// Repeated orientations in the sequence are told apart
+		let mut s = simulation("turn = NENW");
+		assert_eq!(visited(&s), 11);
+		assert_eq!(obstructions(&s), obstructions_simulated(&mut s));
+
+		assert_eq!(simulation("").protocol, Protocol::turn_right());
+		assert!(Protocol::sequence([]).is_none());
+
+		let err = Simulation::read(Input(&(dedent(INPUT_EXAMPLE) + "\nturn = up"))).unwrap_err();
+		assert_eq!((err.line,err.column), (13,8));
+	}
+
+	#[test]
+	fn animation() {
+
+		let mut frames = vec![];
+		animate(&dedent(INPUT_EXAMPLE), 1, &mut |frame| frames.push(frame)).unwrap();
+
+		assert_eq!(frames[0].lines().nth(6).unwrap(), ".#..^.....");
+		assert_eq!(frames[1].lines().nth(5).unwrap(), "....^.....");
+		assert_eq!(frames[1].lines().nth(6).unwrap(), ".#..X.....");
+
+		// the guard leaves the room heading south
+		let last = frames.last().unwrap();
+		assert_eq!(last.lines().nth(9).unwrap(), "......#v..");
+		assert_eq!(last.matches('X').count(), 40);
+
+		let mut frames = vec![];
+		animate(&dedent(INPUT_EXAMPLE), 2, &mut |frame| frames.push(frame)).unwrap();
+
+		let loops = frames.iter().filter(|f| f.ends_with("loop detected")).collect_vec();
+		assert_eq!(loops.len(), 6);
+		assert!(loops.iter().all(|f| f.matches('O').count() == 1));
+		assert!(loops[0].contains("obstacle at "));
+	}
+
 	#[test]
 	fn submit()-> Result<(), AppError> {
-		try_submit(Day(6), solve_1, Part1)?;
-		try_submit(Day(6), solve_2, Part2)?;
+		try_submit(Day(6), solve_1, 1)?;
+		try_submit(Day(6), solve_2, 2)?;
 		Ok(())
 	}
 }
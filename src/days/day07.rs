@@ -1,6 +1,7 @@
 // https://adventofcode.com/2024/day/7
 
 use super::*;
+use crate::grammar::tokens;
 use std::iter::repeat_n;
 
 #[derive(Debug,Clone,Copy)]
@@ -17,84 +18,66 @@ struct Equation {
 }
 
 impl Equation {
-	pub fn try_solve_with(&self,ops:&Vec<Op>) -> Result<usize,()> {
+	/// Tells whether applying the operators, left to right, gives the
+	/// target. Operands too large for the result to fit are an error.
+	pub fn try_solve_with(&self,ops:&[Op]) -> Result<bool,AppError> {
 
 		fn digits(n:usize) -> u32 {
-			1 + n.ilog10()
+			n.checked_ilog10().map_or(1, |d| d + 1)
 		}
 
 		let &first = self.operands.first().expect("There should be a first operand");
 		let result = self.operands.iter()
 			.skip(1).zip(ops)
-			.fold(first,|acc,(n,o)|
+			.try_fold(first,|acc,(&n,o)|
 				match o {
-					Op::Plus => acc + n,
-					Op::Times => acc * n,
-					Op::Concat => acc * 10usize.pow(digits(*n)) + n,
-				}.to_owned()
-			);
-
-		if result == self.target {
-			Ok(result)
-		} else {
-			Err(())
-		}
+					Op::Plus => acc.checked_add(n),
+					Op::Times => acc.checked_mul(n),
+					Op::Concat => 10usize.checked_pow(digits(n))
+						.and_then(|shift| acc.checked_mul(shift))
+						.and_then(|acc| acc.checked_add(n)),
+				}
+			)
+			.ok_or_else(|| AppError::Unsolvable(format!("Operands of the equation for {} are too large", self.target)))?;
+
+		Ok(result == self.target)
 	}
 }
 
-peg::parser!{
-
-	grammar line() for str {
-
-		rule _ = [' ']
-		rule __ = _+
-
-		rule digit() -> char = [c if c.is_ascii_digit()]
-
-		rule number() -> usize
-			= n:$(digit()+) {? n.parse().or(Err("Expected usize value")) }
-
-		/// Matches a line with a target value, followed by ':',
-		/// and a list of whitespace separated numbers
-		pub rule equation() -> Equation
-			= target:number() ":" _ operands:(number() **<2,> _) { Equation { target, operands } }
-	}
+/// Matches a line with a target value, followed by ':',
+/// and a list of whitespace separated numbers
+fn equation(line:&str) -> Result<Equation,ParseError<LineCol>> {
+	tokens::labelled_list(line, ' ').map(|(target,operands)| Equation { target, operands })
 }
 
-fn solve_1(input: &str) -> String {
+/// Sums the targets of the equations that some combination of operators solves
+fn calibration(equations:impl Iterator<Item=Result<Equation,AppError>>, ops:&[Op]) -> Result<usize,AppError> {
 
-	let equations = Input(input).parse_iter(line::equation);
-	let ops = [Op::Plus,Op::Times];
-
-	equations.filter_map(|eq| {
+	equations.map(|eq| {
+		let eq = eq?;
 		let total_ops = eq.operands.len()- 1;
-		let mut op_combos = repeat_n(ops.iter().copied(),total_ops).multi_cartesian_product();
-		let found = op_combos.any(|ref ops| eq.try_solve_with(ops).is_ok());
-		if found { Some(eq.target) } else { None }
+		let op_combos = repeat_n(ops.iter().copied(),total_ops).multi_cartesian_product();
+		// the first combination solving it, or the first error
+		let found = op_combos.map(|ops| eq.try_solve_with(&ops)).find(|solved| !matches!(solved, Ok(false)));
+		Ok(if found.transpose()?.is_some() { eq.target } else { 0 })
 	})
-	.sum::<usize>()
-	.to_string()
+	.process_results(|targets| targets.sum::<usize>())
 }
 
-fn solve_2(input: &str) -> String {
-
-	let equations = Input(input).parse_iter(line::equation);
-	let ops_ext = [Op::Plus,Op::Times,Op::Concat];
+pub fn solve_1(source:impl Source) -> Result<String,AppError> {
+	let equations = source.parse_iter(equation);
+	Ok(calibration(equations, &[Op::Plus,Op::Times])?.to_string())
+}
 
-	equations.filter_map(|eq| {
-		let total_ops = eq.operands.len()- 1;
-		let mut op_combos = repeat_n(ops_ext.iter().copied(),total_ops).multi_cartesian_product();
-		let found =	op_combos.any(|ref ops| eq.try_solve_with(ops).is_ok());
-		if found { Some(eq.target) } else { None }
-	})
-	.sum::<usize>()
-	.to_string()
+pub fn solve_2(source:impl Source) -> Result<String,AppError> {
+	let equations = source.parse_iter(equation);
+	Ok(calibration(equations, &[Op::Plus,Op::Times,Op::Concat])?.to_string())
 }
 
+#[cfg(test)]
 mod test {
 
 	use super::*;
-	use aoc_driver::Part::*;
 
 	const INPUT_EXAMPLE:&str =
 		r###"
@@ -112,21 +95,34 @@ mod test {
 	#[test]
 	fn part_1_example() {
 		let expected = "3749";
-		let actual = solve_1(INPUT_EXAMPLE);
+		let actual = solve_1(&Input(&dedent(INPUT_EXAMPLE))).unwrap();
 		assert_eq!(actual,expected);
 	}
 
 	#[test]
 	fn part_2_example() {
 		let expected = "11387";
-		let actual = solve_2(INPUT_EXAMPLE);
+		let actual = solve_2(&Input(&dedent(INPUT_EXAMPLE))).unwrap();
 		assert_eq!(actual,expected);
 	}
 
+	#[test]
+	fn bad_input() {
+
+		// a zero has one digit too
+		assert_eq!(solve_2(&Input("1: 0 0")).unwrap(), "0");
+		assert_eq!(solve_2(&Input("10: 1 0")).unwrap(), "10");
+
+		let input = format!("1: {} 10", usize::MAX);
+		assert!(matches!(solve_2(&Input(&input)), Err(AppError::Unsolvable(_))));
+		assert!(matches!(solve_1(&Input(&input)), Err(AppError::Unsolvable(_))));
+	}
+
+
 	#[test]
 	fn submit()-> Result<(), AppError> {
-		try_submit(Day(7), solve_1, Part1)?;
-		try_submit(Day(7), solve_2, Part2)?;
+		try_submit(Day(7), |input| solve_1(&Input(input)), 1)?;
+		try_submit(Day(7), |input| solve_2(&Input(input)), 2)?;
 		Ok(())
 	}
 }
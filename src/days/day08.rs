@@ -1,17 +1,15 @@
 // https://adventofcode.com/2024/day/8
 
-use std::ops::Deref;
-
 use super::*;
 
 #[derive(Clone, Copy)]
 struct Antenna {
 	frequency:u8,
-	location:Position
+	location:V2
 }
 
 impl Antenna {
-	pub fn at_location(self, location:Position) -> Self {
+	pub fn at_location(self, location:V2) -> Self {
 		Self { location, ..self}
 	}
 }
@@ -21,136 +19,103 @@ impl TryFrom<u8> for Antenna {
 
 	fn try_from(code: u8) -> Result<Self, Self::Error> {
 		if code.is_ascii_alphanumeric() {
-			Ok(Antenna { frequency: code, location: Default::default() })
+			Ok(Antenna { frequency: code, location: V2::zero() })
 		} else {
 			Err(())
 		}
 	}
 }
 
-impl From<Antenna> for V2 {
-	fn from(antenna: Antenna) -> Self {
-		V2::from(antenna.location)
-	}
-}
-
-struct City(Map<u8>);
+/// A city laid out on any kind of grid,
+/// whose cells are all within the city limits
+struct City<G>(G);
 
-impl Deref for City {
-	type Target = Map;
+impl<G:Grid<Cell=u8>> City<G> {
 
-	fn deref(&self) -> &Self::Target {
-		&self.0
+	pub fn contains(&self, location:V2) -> bool {
+		self.0.cell(location).is_some()
 	}
-}
 
-impl City {
-	pub fn antennae<'a>(&'a self) -> impl Iterator<Item=Antenna> + 'a {
-		let position_of = |o| self.position_of(o);
-		self.iter().enumerate()
-			.filter_map(move |(offset,byte)| {
+	pub fn antennae(&self) -> impl Iterator<Item=Antenna> {
+		self.0.cells()
+			.filter_map(|(location,byte)| {
 				Antenna::try_from(byte)
-					.map(|a| a.at_location(position_of(offset).unwrap()))
+					.map(|a| a.at_location(location))
 					.ok()
 			})
 	}
 
-	pub fn frequencies<'a>(&'a self) -> impl Iterator<Item=u8> + 'a {
+	pub fn frequencies(&self) -> impl Iterator<Item=u8> {
 		self.antennae().map(|a| a.frequency).sorted().dedup()
 	}
 }
 
-fn solve_1(input: &str) -> String {
-
-	let city = City(Map::from(Input(input).lines()));
-
-	// Gather all pairs of antennae with the same frequency
-
-	let antenna_combos = city.frequencies().flat_map(|c| {
-		city.antennae().filter(move |a| a.frequency == c).combinations(2)
-	});
-
-	// Find both potential anti-nodes for each pair
-
-	let anti_nodes = antenna_combos.flat_map(|c| {
-		c.into_iter().map(V2::from).permutations(2).map(|vecs| vecs[1] - vecs[0] + vecs[1])
-	});
-
-	// Reduce the list of potential anti-nodes
-	// Must be inside the bounds, and they must
-	// be counted once (no dupes)
-
-	let max:V2 = city.last_position().into();
-	let min:V2 = V2::zero();
-
-	anti_nodes
-		.sorted()
-		.dedup()
-		.filter(|v| match *v {
-
-			V2{x,..} if x < min.x => false,
-			V2{x,..} if x > max.x => false,
-
-			V2{y,..} if y < min.y => false,
-			V2{y,..} if y > max.y => false,
+impl<G:Grid<Cell=u8>> City<G> {
 
-			_ => true
+	/// Gather all pairs of antennae with the same frequency
+	fn antenna_pairs(&self) -> impl Iterator<Item=(Antenna,Antenna)> {
+		self.frequencies().flat_map(move |c| {
+			self.antennae().filter(move |a| a.frequency == c).combinations(2)
+				.map(|pair| (pair[0],pair[1]))
 		})
-		.count()
-		.to_string()
-
-}
-
-fn solve_2(input: &str) -> String {
+	}
 
-	let city = City(Map::from(Input(input).lines()));
+	/// Locations of the anti-nodes, twice as far from
+	/// an antenna as from the other one
+	pub fn antinodes(&self) -> impl Iterator<Item=V2> {
 
-	fn antinodes(a:V2,b:V2,city:&City) -> impl IntoIterator<Item=Position> {
+		// Find both potential anti-nodes for each pair
 
-		let max:V2 = city.last_position().into();
-		let min:V2 = V2::zero();
-		let inc = b-a;
-		let mut antinode = b;
-		// antinodes generated from antennae a -> b
-		// that fall inside the city limits
-		std::iter::from_fn(move || {
-			antinode = antinode+inc;
-			match antinode {
-				V2{x,..} if x < min.x => None,
-				V2{x,..} if x > max.x => None,
+		let anti_nodes = self.antenna_pairs().flat_map(|(a,b)| {
+			let (a,b) = (a.location,b.location);
+			[b - a + b, a - b + a]
+		});
 
-				V2{y,..} if y < min.x => None,
-				V2{y,..} if y > max.y => None,
+		// Reduce the list of potential anti-nodes
+		// Must be inside the bounds, and they must
+		// be counted once (no dupes)
 
-				_ => Some(antinode)
-			}
-		})
-		// Plus the two antennae
-		.chain([a,b].into_iter()).map(Position::try_from).flatten()
+		anti_nodes
+			.filter(|&v| self.contains(v))
+			.sorted()
+			.dedup()
 	}
 
-	// Gather all pairs of antennae with the same frequency
+	/// Locations of the anti-nodes, taking resonant harmonics into account
+	pub fn harmonics(&self) -> impl Iterator<Item=V2> {
+
+		// Any grid location in line with both antennae,
+		// including them, that falls inside the city limits
+		let in_line = |a:V2,step:V2| {
+			std::iter::successors(Some(a), move |&v| Some(v + step))
+				.take_while(|&v| self.contains(v))
+		};
 
-	let antenna_combos = city.frequencies().flat_map(|c| {
-		city.antennae().filter(move |a| a.frequency == c).combinations(2)
-	});
+		let anti_nodes = self.antenna_pairs().flat_map(move |(a,b)| {
+			let step = (b.location - a.location).reduced();
+			in_line(a.location, step).chain(in_line(a.location, -step))
+		});
 
-	let anti_nodes = antenna_combos.flat_map(|c| {
-		c.into_iter().map(V2::from).permutations(2).flat_map(|vecs| antinodes(vecs[0], vecs[1], &city))
-	});
+		anti_nodes
+			.sorted()
+			.dedup()
+	}
+}
 
-	anti_nodes
-		.sorted()
-		.dedup()
-		.count()
-		.to_string()
+pub fn solve_1(input: &str) -> Result<String,AppError> {
+	let city = City(Map::read(Input(input).lines())?);
+	Ok(city.antinodes().count().to_string())
 }
 
+pub fn solve_2(input: &str) -> Result<String,AppError> {
+	let city = City(Map::read(Input(input).lines())?);
+	Ok(city.harmonics().count().to_string())
+}
 
+#[cfg(test)]
 mod test {
 
 	use super::*;
-	use aoc_driver::Part::*;
 
 	const INPUT_EXAMPLE:&str =
 		r###"
@@ -172,7 +137,7 @@ mod test {
 	fn part_1_example() {
 
 		let expected = "14";
-		let actual = solve_1(INPUT_EXAMPLE);
+		let actual = solve_1(&dedent(INPUT_EXAMPLE)).unwrap();
 
 		assert_eq!(actual,expected);
 	}
@@ -181,15 +146,25 @@ mod test {
 	fn part_2_example() {
 
 		let expected = "34";
-		let actual = solve_2(INPUT_EXAMPLE);
+		let actual = solve_2(&dedent(INPUT_EXAMPLE)).unwrap();
 
 		assert_eq!(actual,expected);
 	}
 
+	#[test]
+	fn sparse() {
+
+		let dense = City(Map::read(Input(&dedent(INPUT_EXAMPLE)).lines()).unwrap());
+		let sparse = City(Sparse::<u8>::read(Input(&dedent(INPUT_EXAMPLE)).lines()));
+
+		assert_eq!(sparse.antinodes().collect_vec(), dense.antinodes().collect_vec());
+		assert_eq!(sparse.harmonics().collect_vec(), dense.harmonics().collect_vec());
+	}
+
 	#[test]
 	fn submit()-> Result<(), AppError> {
-		try_submit(Day(8), solve_1, Part1)?;
-		try_submit(Day(8), solve_2, Part2)?;
+		try_submit(Day(8), solve_1, 1)?;
+		try_submit(Day(8), solve_2, 2)?;
 		Ok(())
 	}
 }
@@ -68,30 +68,40 @@ impl Span {
 struct Disk(Vec<Block>);
 
 impl Disk {
-	pub fn new(map:&str) -> Self {
+	pub fn new(map:Line) -> Result<Self,InputError> {
+		// Block counts, one digit each
+		let counts:Vec<usize> = map.raw.chars().enumerate()
+			.map(|(at,c)| {
+				c.to_digit(10)
+					.map(|d| d as usize)
+					.ok_or_else(|| map.error(at, format!("Expected a digit, found '{c}'")))
+			})
+			.try_collect()?;
 		// Precompute expanded size
-		let cap = map.chars().fold(0,|sum,c| sum + c.to_digit(10).unwrap() as usize);
+		let cap = counts.iter().sum();
+		if cap == 0 {
+			return Err(map.error(0, "The disk should have at least one block"))
+		}
 		// Expand map into individual blocks
-		map.chars()
+		counts
 			.chunks(2)
-			.into_iter()
 			.enumerate()
-			.fold(Disk(Vec::with_capacity(cap)),|mut disk,(i,mut chunk)| {
+			.try_fold(Disk(Vec::with_capacity(cap)),|mut disk,(i,chunk)| {
 				// initialize used blocks
-				if let Some(chr) = chunk.next() {
-					let count = chr.to_digit(10).unwrap() as usize;
-					let file_id = FileId::try_from(i as u16).unwrap();
+				if let Some(&count) = chunk.first() {
+					let file_id = u16::try_from(i).ok()
+						.and_then(|i| FileId::try_from(i).ok())
+						.ok_or_else(|| map.error(2*i, "Too many files"))?;
 					let new_len = disk.len() + count;
 					disk.resize_with(new_len,||Block::Used(file_id));
 				}
 				// initialize unused blocks
 				// The examples do not have a last chunk of unused
-				if let Some(chr) = chunk.next() {
-					let count = chr.to_digit(10).unwrap() as usize;
+				if let Some(&count) = chunk.get(1) {
 					let new_len = disk.len() + count;
 					disk.resize_with(new_len,||Block::Unused);
 				}
-				disk
+				Ok(disk)
 			})
 	}
 
@@ -127,7 +137,9 @@ impl Disk {
 
 			let relocate_to = {
 				let mut free_spans = self.spans(Some(fragged.clone())).filter(|Span{kind,..}| matches!(kind,Block::Unused)).peekable();
-				fragged.start = *free_spans.peek().unwrap().range.start();
+				// No free space left at all
+				let Some(free) = free_spans.peek() else { break };
+				fragged.start = *free.range.start();
 				free_spans.find(|span| span.len() >= file.len())
 			};
 
@@ -159,7 +171,7 @@ impl Disk {
 		// Not really :D
 		let len = src.end() - src.start();
 		let dst = to..=to+len;
-		for (s,d) in src.into_iter().zip(dst.into_iter()) {
+		for (s,d) in src.into_iter().zip(dst) {
 			self[d] = self[s];
 			self[s] = Block::Unused;
 		}
@@ -191,39 +203,41 @@ impl DerefMut for Disk {
 	}
 }
 
-fn solve_1(input: &str) -> String {
+pub fn solve_1(input: &str) -> Result<String,AppError> {
 
-	let map = Input(input).lines().take(1).collect::<String>();
-	let mut disk = Disk::new(&map);
+	let map = Input(input).first_line()?;
+	let mut disk = Disk::new(map)?;
 	disk.pack();
-	disk.checksum().to_string()
+	Ok(disk.checksum().to_string())
 }
 
-fn solve_2(input: &str) -> String {
+pub fn solve_2(input: &str) -> Result<String,AppError> {
 
-	let map = Input(input).lines().take(1).collect::<String>();
-	let mut disk = Disk::new(&map);
+	let map = Input(input).first_line()?;
+	let mut disk = Disk::new(map)?;
 	disk.defrag();
-	disk.checksum().to_string()
+	Ok(disk.checksum().to_string())
 }
 
 #[cfg(test)]
 mod test {
 
 	use super::*;
-	use aoc_driver::Part::*;
-
-	impl Disk {
-		fn show(&self,sep:&str) -> String {
-			self.iter().map(|block| {
-				match block {
-					Block::Unused => '.',
-					Block::Used(fid) => u16::from(fid).to_string().chars().next().unwrap()
-				}
-			}).join(sep)
+
+	/// Free blocks as '.', used ones as the last digit of their file id
+	impl From<Block> for char {
+		fn from(block:Block) -> Self {
+			match block {
+				Block::Unused => '.',
+				Block::Used(fid) => char::from(b'0' + (u16::from(&fid) % 10) as u8)
+			}
 		}
 	}
 
+	fn blocks(disk:&Disk) -> Map<Block> {
+		Map::from(disk.to_vec())
+	}
+
 	const INPUT_EXAMPLE:&str =
 		r###"
 		2333133121414131402
@@ -232,37 +246,36 @@ mod test {
 	#[test]
 	fn disk() {
 
-		let map = Input(INPUT_EXAMPLE).lines().take(1).collect::<String>();
-		let mut disk = Disk::new(&map);
+		let input = dedent(INPUT_EXAMPLE);
+		let map = Input(&input).first_line().unwrap();
+		let mut disk = Disk::new(map).unwrap();
 
 		// Test initialization
 
-		let actual = disk.show("");
 		let expected = "00...111...2...333.44.5555.6666.777.888899";
 
-		assert_eq!(actual,expected);
+		assert_grid_eq(blocks(&disk), expected);
 
 		// Moving blocks
 
 		disk.move_blocks(40..=41, 2);
 
 		let expected = "0099.111...2...333.44.5555.6666.777.8888..";
-		let actual = disk.show("");
 
-		assert_eq!(actual,expected);
+		assert_grid_eq(blocks(&disk), expected);
 	}
 
 	#[test]
 	fn part_1_example() {
 
-		let map = Input(INPUT_EXAMPLE).lines().take(1).collect::<String>();
-		let mut disk = Disk::new(&map);
+		let input = dedent(INPUT_EXAMPLE);
+		let map = Input(&input).first_line().unwrap();
+		let mut disk = Disk::new(map).unwrap();
 
 		disk.pack();
-		let actual = disk.show("");
 		let expected = "0099811188827773336446555566..............";
 
-		assert_eq!(actual,expected);
+		assert_grid_eq(blocks(&disk), expected);
 
 		let actual = disk.checksum().to_string();
 		let expected = "1928";
@@ -272,21 +285,40 @@ mod test {
 
 	#[test]
 	fn part_2_example() {
-		let map = Input(INPUT_EXAMPLE).lines().take(1).collect::<String>();
-		let mut disk = Disk::new(&map);
+		let input = dedent(INPUT_EXAMPLE);
+		let map = Input(&input).first_line().unwrap();
+		let mut disk = Disk::new(map).unwrap();
 		disk.defrag();
 
 		let expected = "00992111777.44.333....5555.6666.....8888..";
-		let actual = disk.show("");
 
-		assert_eq!(actual,expected);
+		assert_grid_eq(blocks(&disk), expected);
 
 	}
 
+	#[test]
+	fn bad_input() {
+
+		let Err(AppError::BadInput(err)) = solve_1("12é4") else {
+			panic!("Input should be rejected")
+		};
+		assert_eq!((err.line,err.column), (1,3));
+		assert!(err.reason.contains("'é'"));
+
+		// nothing to pack
+		assert!(matches!(solve_1("0"), Err(AppError::BadInput(_))));
+		assert!(matches!(solve_2("00"), Err(AppError::BadInput(_))));
+
+		// nowhere to move files to
+		assert_eq!(solve_1("1").unwrap(), "0");
+		assert_eq!(solve_2("1").unwrap(), "0");
+		assert_eq!(solve_2("21").unwrap(), "0");
+	}
+
 	#[test]
 	fn submit()-> Result<(), AppError> {
-		try_submit(Day(9), solve_1, Part1)?;
-		try_submit(Day(9), solve_2, Part2)?;
+		try_submit(Day(9), solve_1, 1)?;
+		try_submit(Day(9), solve_2, 2)?;
 		Ok(())
 	}
 }
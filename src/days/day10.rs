@@ -4,20 +4,20 @@ use std::{fmt::Debug, ops::Deref};
 
 use super::*;
 
-/// Paths can turn in 90 degrees only
-/// in horizontal and vertical directions
+// Paths can turn in 90 degrees only
+// in horizontal and vertical directions
 
-/// A trail is a path from level 0 to 9 increasing by 1
-///
-/// A trailhead is any location with height 0
-/// Its score is the number of trails (with slope 1)
-/// that reach the top (a 9 of height)
+// A trail is a path from level 0 to 9 increasing by 1
+//
+// A trailhead is any location with height 0
+// Its score is the number of trails (with slope 1)
+// that reach the top (a 9 of height)
 
-/// Strategy:
-/// - Find trailheads level locations
-/// - scan around for locations with level + 1
-/// - Repeat 8 times
-/// - Spots remaining are peaks
+// Strategy:
+// - Find trailheads level locations
+// - scan around for locations with level + 1
+// - Repeat 8 times
+// - Spots remaining are peaks
 
 #[derive(Debug,Default,Clone,Copy,PartialEq,Eq,PartialOrd,Ord)]
 struct Level(u8);
@@ -52,12 +52,15 @@ impl Level {
 	}
 }
 
-impl From<u8> for Level {
-	fn from(value:u8) -> Self {
-		// value is expected to be an ASCII digit
-		assert!((b'0'..=b'9').contains(&value));
-		let num = value-b'0';
-		Level(num)
+impl TryFrom<u8> for Level {
+	type Error = String;
+
+	fn try_from(value:u8) -> Result<Self,Self::Error> {
+		if value.is_ascii_digit() {
+			Ok(Level(value-b'0'))
+		} else {
+			Err(format!("Expected a level digit, found '{}'", value as char))
+		}
 	}
 }
 
@@ -72,48 +75,19 @@ impl Deref for TopographicMap {
 
 impl TopographicMap {
 
-	const DIRECTIONS:[Direction;4] = const {
-		use Direction::*;
-		[North,East,South,West]
-	};
-
-	pub fn at(&self,coord: impl Into<V2>) -> Option<Spot> {
-		let coord:V2 = coord.into();
-		if self.contains(coord) {
-			let location:Position = coord.try_into().ok()?;
-			let level:Level = self[location];
-			Some(Spot { location, level })
-		} else {
-			None
-		}
+	pub fn at(&self,location:Position) -> Option<Spot> {
+		let level:Level = *self.get(location)?;
+		Some(Spot { location, level })
 	}
 
 	/// Returns locations of trailheads
 	fn trailheads(&self) -> impl Iterator<Item=Spot> {
-		self.iter()
-			.enumerate()
-			.filter_map(|(i,level)| {
-				if level == Level::MIN {
-					let location = self.position_of(i).unwrap();
-					Some(Spot{location,level})
-				} else {
-					None
-				}
-			})
+		self.positions_of(Level::MIN)
+			.map(|location| Spot{location,level:Level::MIN})
 	}
 
 	fn neighbors(&self,spot:Spot) -> impl Iterator<Item=Spot> {
-
-		let coord:V2 = spot.location.into();
-
-		Self::DIRECTIONS
-			.into_iter()
-			.filter_map(move |dir| {
-				let delta:V2 = dir.into();
-				let next = coord + delta;
-				// A spot there, which is next level
-				self.at(next)
-			})
+		self.neighbors_4(spot.location).filter_map(|location| self.at(location))
 	}
 
 	fn paths(&self,spot:Spot) -> impl Iterator<Item=Spot> {
@@ -125,7 +99,7 @@ impl TopographicMap {
 		// or more suitable neighbors
 		self.neighbors(spot)
 			// empty() if no next level ↴
-			.zip(std::iter::repeat_n(next_level,Self::DIRECTIONS.len()).flatten())
+			.zip(std::iter::repeat_n(next_level,Direction::ALL.len()).flatten())
 			.filter_map(|(spot,lvl)| {
 				// A spot there, which is next level
 				Some(spot).filter(|s|s.level == lvl)
@@ -145,23 +119,15 @@ impl TopographicMap {
 	}
 }
 
-impl Display for TopographicMap {
-	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		let csize:usize = self.stride().into();
-		let msg = self.iter().chunks(csize).into_iter()
-			.map(|cnk| {
-				cnk.into_iter().flat_map(|level| char::from_digit(*level as u32,10)).join("")
-			})
-			.collect_vec()
-		;
-		write!(f, "{:#?}", msg)
+impl From<Level> for char {
+	fn from(level: Level) -> Self {
+		char::from(b'0' + *level)
 	}
 }
 
-impl<'a, L:Iterator<Item=&'a str>+Clone> From<L> for TopographicMap {
-	fn from(lines: L) -> Self {
-		let map:Map<Level> = lines.into();
-		Self(map)
+impl TopographicMap {
+	fn read(input:Input) -> Result<Self,InputError> {
+		input.map(Level::try_from).map(Self)
 	}
 }
 
@@ -183,40 +149,39 @@ impl Debug for Spot {
 	}
 }
 
-fn solve_1(input: &str) -> String {
+pub fn solve_1(input: &str) -> Result<String,AppError> {
 
-	let map = TopographicMap::from(Input(input).lines());
+	let map = TopographicMap::read(Input(input))?;
 
-	map.trailheads()
+	let sum = map.trailheads()
 		.map(|h| {
 			map.climb(h)
 				.map(|s| s.location)
 				.unique()
 				.count()
 		})
-		.sum::<usize>()
-		.to_string()
+		.sum::<usize>();
+
+	Ok(sum.to_string())
 }
 
-fn solve_2(input: &str) -> String {
+pub fn solve_2(input: &str) -> Result<String,AppError> {
 
-	let map = TopographicMap::from(Input(input).lines());
+	let map = TopographicMap::read(Input(input))?;
 
-	map.trailheads()
+	let sum = map.trailheads()
 		.map(|h| {
-			map.climb(h)
-				.map(|s| s.location)
-				.count()
+			map.climb(h).count()
 		})
-		.sum::<usize>()
-		.to_string()
+		.sum::<usize>();
+
+	Ok(sum.to_string())
 }
 
 #[cfg(test)]
 mod test {
 
 	use super::*;
-	use aoc_driver::Part::*;
 
 	const INPUT_EXAMPLE:&str =
 		r###"
@@ -233,32 +198,32 @@ mod test {
 	#[test]
 	fn topographic_map() {
 
-		let map = TopographicMap::from(Input(INPUT_EXAMPLE).lines());
+		let map = TopographicMap::read(Input(&dedent(INPUT_EXAMPLE))).unwrap();
 
 		// top-left corner
-		let spot = map.at(V2{x:0,y:0}).unwrap();
+		let spot = map.at(Position{x:0,y:0}).unwrap();
 		let actual = map.paths(spot).map(|s| s.location).at_most_one().ok().flatten();
 		let expected = Some(Position{x:1,y:0});
 		assert_eq!(actual,expected);
 
 		// a nine
-		let spot = map.at(V2{x:1,y:0}).unwrap();
+		let spot = map.at(Position{x:1,y:0}).unwrap();
 		let mut actual = map.paths(spot).map(|s| s.location);
 		assert!(actual.next().is_none());
 
 		// no suitable neighbors
-		let spot = map.at(V2{x:0,y:7}).unwrap();
+		let spot = map.at(Position{x:0,y:7}).unwrap();
 		let mut actual = map.paths(spot).map(|s| s.location);
 		assert!(actual.next().is_none());
 
 		// bottom right corner
-		let spot = map.at(V2{x:7,y:7}).unwrap();
+		let spot = map.at(Position{x:7,y:7}).unwrap();
 		let actual = map.paths(spot).map(|s| s.location).at_most_one().ok().flatten();
 		let expected = Some(Position{x:6,y:7});
 		assert_eq!(actual,expected);
 
 		// some trailhead
-		let spot = map.at(V2{x:0,y:6}).unwrap();
+		let spot = map.at(Position{x:0,y:6}).unwrap();
 		let actual = map.paths(spot).map(|s| s.location).collect_tuple();
 		let expected = Some((Position{x:1,y:6},Position{x:0,y:7}));
 		assert_eq!(actual,expected);
@@ -286,24 +251,63 @@ mod test {
 		assert!(locations.contains(&Position{x:1,y:7}));
 	}
 
+	#[test]
+	fn trails() {
+
+		let map = TopographicMap::read(Input(&dedent(INPUT_EXAMPLE))).unwrap();
+		let head = map.at(Position{x:2,y:0}).unwrap();
+
+		// every spot reached while climbing from the trailhead
+		let climbed = std::iter::successors(Some(vec![head]), |spots| {
+			Some(spots.iter().flat_map(|&s| map.paths(s)).collect_vec()).filter(|s| !s.is_empty())
+		});
+
+		let view = map.view(|_| '.')
+			.overlay(climbed.flatten().map(|s| (s.location, char::from(s.level))));
+
+		assert_grid_eq(view, "
+			.901....
+			.812....
+			8743....
+			96549...
+			..6789..
+			....9...
+			........
+			........
+		");
+
+		// peaks only, over the whole map
+		let peaks = map.climb(head).map(|s| s.location);
+		assert_grid_eq(map.view(char::from).highlight(peaks, '*'), "
+			8*010123
+			78121874
+			87430965
+			*654*874
+			45678*03
+			3201*012
+			01329801
+			10456732
+		");
+	}
+
 	#[test]
 	fn part_1_example() {
-		let actual = solve_1(INPUT_EXAMPLE);
+		let actual = solve_1(&dedent(INPUT_EXAMPLE)).unwrap();
 		let expected = "36";
 		assert_eq!(actual,expected);
 	}
 
 	#[test]
 	fn part_2_example() {
-		let actual = solve_2(INPUT_EXAMPLE);
+		let actual = solve_2(&dedent(INPUT_EXAMPLE)).unwrap();
 		let expected = "81";
 		assert_eq!(actual,expected);
 	}
 
 	#[test]
 	fn submit()-> Result<(), AppError> {
-		try_submit(Day(10), solve_1, Part1)?;
-		try_submit(Day(10), solve_2, Part2)?;
+		try_submit(Day(10), solve_1, 1)?;
+		try_submit(Day(10), solve_2, 2)?;
 		Ok(())
 	}
 }
@@ -1,84 +1,122 @@
 // https://adventofcode.com/2024/day/11
 
-use std::{num::ParseIntError, ops::Deref};
+use std::{collections::HashMap, hash::Hash, num::ParseIntError, ops::Deref};
 
 use super::*;
 
-trait Evolve: Copy + From<usize> + ToString {
-	fn evolve(&self) -> Evolution<Self>;
-}
+peg::parser!{
+
+	grammar rules() for str {
+
+		rule _ = [' ' | '\t']*
+
+		rule comment() = "#" [_]*
 
-// The length is provided when it's created
-#[derive(Debug,Clone,Copy,PartialEq)]
-struct SizedStone(usize,u8);
+		rule number() -> usize
+			= n:$(['0'..='9']+) {? n.parse().or(Err("Expected usize value")) }
 
-impl TryFrom<&str> for SizedStone {
-	type Error = ();
+		rule split() -> Split
+			= "halves" { Split::Halves }
+			/ "never" { Split::Never }
+			/ "last" _ n:number() {? u32::try_from(n).map(Split::Last).or(Err("Expected digit count")) }
 
-	fn try_from(input: &str) -> Result<Self, Self::Error> {
-		let len = u8::try_from(input.len()).map_err(|_|())?;
-		let val = str::parse(input).map_err(|_|())?;
-		Ok(SizedStone(val,len))
+		rule setting() -> Setting
+			= "zero" _ "=" _ n:number() { Setting::Zero(n) }
+			/ "split" _ "=" _ s:split() { Setting::Split(s) }
+			/ "multiply" _ "=" _ n:number() { Setting::Multiply(n) }
+			/ "blinks" _ "=" _ n:number() { Setting::Blinks(n) }
+
+		/// Matches a `key = value` line, or a comment line
+		pub rule line() -> Option<Setting>
+			= _ s:setting() _ comment()? { Some(s) }
+			/ _ comment()? { None }
 	}
 }
 
-impl From<usize> for SizedStone {
-	fn from(value: usize) -> Self {
-		let len = 1 + if value >= 10 { value.ilog10() as u8 } else { 0 };
-		Self(value,len)
-	}
+/// How stones with no zero on them are split in two
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+enum Split {
+	/// Numbers with an even amount of digits split into
+	/// their upper and lower halves (the puzzle rule)
+	Halves,
+	/// Numbers with more than this amount of digits
+	/// lose them to a new stone
+	Last(u32),
+	/// Stones never split
+	Never,
 }
 
-impl From<(usize,u8)> for SizedStone {
-	fn from((val,len): (usize,u8)) -> Self {
-		Self(val,len)
-	}
+enum Setting {
+	Zero(usize),
+	Split(Split),
+	Multiply(usize),
+	Blinks(usize),
 }
 
-impl SizedStone {
-	#[inline]
-	pub fn try_split(&self) -> Option<((usize,u8),usize)> {
-
-		let SizedStone(val,len) = self;
-
-		if len % 2 == 0 {
-			let half = len/2;
-			let n = 10usize.pow(half as u32);
-			let upper = val/n;
-			let lower = val%n;
-			Some((
-				(upper, half),
-				// leading zeroes are eliminated!
-				// or it can be just 0
-				lower
-			))
-		} else {
-			None
-		}
-	}
+/// Rules applied to every stone on each blink, the first that matches wins:
+///
+/// 1. A stone with a 0 is replaced by one with `zero`
+/// 2. A stone is split in two, according to `split`
+/// 3. The number on the stone is multiplied by `multiplier`
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+struct Rules {
+	zero: usize,
+	split: Split,
+	multiplier: usize,
 }
 
-impl Evolve for SizedStone {
-	fn evolve(&self) -> Evolution<Self> {
-		use Evolution::*;
-		let v = self.0;
-		if v == 0 {
-			Single(Self(1,1))
-		} else if let Some((fst,snd)) = self.try_split() {
-			Couple(fst.into(),snd.into())
-		} else {
-			Single(Self::from(v*2024))
-		}
+impl Default for Rules {
+	fn default() -> Self {
+		Rules { zero: 1, split: Split::Halves, multiplier: 2024 }
 	}
 }
 
-impl Display for SizedStone {
-	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		write!(f,"{}",self.0)
+/// A variant of the puzzle: its rules, and how many times
+/// to blink, when it differs from the part being solved
+#[derive(Debug,Clone,Copy,Default,PartialEq,Eq)]
+struct Variant {
+	rules: Rules,
+	blinks: Option<usize>,
+}
+
+impl Variant {
+
+	/// Reads settings from lines such as:
+	///
+	/// ```text
+	/// # settings not given keep their default
+	/// zero = 1
+	/// split = halves   # or `never`, or `last 3`
+	/// multiply = 2024
+	/// blinks = 25
+	/// ```
+	fn read<'a>(lines:impl Iterator<Item=Line<'a>>) -> Result<Self,InputError> {
+		lines
+			.map(|line| line.parse(rules::line))
+			.process_results(|settings| {
+				settings.flatten().fold(Self::default(), |Variant { rules, blinks }, setting| match setting {
+					Setting::Zero(zero) => Variant { rules: Rules { zero, ..rules }, blinks },
+					Setting::Split(split) => Variant { rules: Rules { split, ..rules }, blinks },
+					Setting::Multiply(multiplier) => Variant { rules: Rules { multiplier, ..rules }, blinks },
+					Setting::Blinks(n) => Variant { rules, blinks: Some(n) },
+				})
+			})
 	}
 }
 
-#[derive(Debug,Clone,Copy,PartialEq)]
+/// Reported when the number on a stone, or the amount
+/// of stones, no longer fits after some blink
+fn overflow(blink:usize) -> AppError {
+	AppError::Unsolvable(format!("Stones grow too large to be counted at blink {blink}"))
+}
+
+trait Evolve: Copy + Eq + Hash + From<usize> + ToString {
+	/// The stones replacing this one after a blink, or
+	/// `None` when their numbers don't fit
+	fn evolve(&self, rules:&Rules) -> Option<Evolution<Self>>;
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
 struct Stone(usize);
 impl From<usize> for Stone {
 	fn from(value: usize) -> Self {
@@ -138,14 +176,17 @@ impl From<Stone> for String {
 
 impl Stone {
 	#[inline]
-	pub fn try_split(&self) -> Option<(usize,usize)> {
+	pub fn try_split(&self, split:Split) -> Option<(usize,usize)> {
 		let v = self.0;
-		if v < 10 {
+		if let Split::Last(digits) = split {
+			let n = 10usize.checked_pow(digits)?;
+			(v >= n).then(|| (v/n, v%n))
+		} else if v < 10 || split == Split::Never {
 			None
 		} else {
 			// 1 + n (where 10^n = v)
 			let digits = 1 + v.ilog10();
-			if digits % 2 == 0 {
+			if digits.is_multiple_of(2) {
 				let n = 10usize.pow(digits/2);
 				let upper = v/n;
 				let lower = v%n;
@@ -164,93 +205,301 @@ impl Display for Stone {
 }
 
 impl Evolve for Stone {
-	fn evolve(&self) -> Evolution<Stone> {
+	fn evolve(&self, rules:&Rules) -> Option<Evolution<Stone>> {
 		use Evolution::*;
 		let v = self.0;
 		if v == 0 {
-			Single(Stone(1))
-		} else if let Some((a,b)) = self.try_split() {
-			Couple(Stone(a),Stone(b))
+			Some(Single(Stone(rules.zero)))
+		} else if let Some((a,b)) = self.try_split(rules.split) {
+			Some(Couple(Stone(a),Stone(b)))
 		} else {
-			Single(Stone(v*2024))
+			v.checked_mul(rules.multiplier).map(|v| Single(Stone(v)))
 		}
 	}
 }
 
 struct Blinker<T> {
-	seed: Vec<T>
+	seed: Vec<T>,
+	rules: Rules,
 }
 
 impl<T:Evolve> Blinker<T> {
 
-	fn count<const TIMES:u8>(self) -> usize {
-
-		fn walk<T:Evolve,const MAX:u8>(t:impl Iterator<Item=T>,i:u8) -> usize {
-			if i < MAX {
-				t.map(|s| walk::<T,MAX>(s.evolve(),i+1)).sum()
-			} else {
-				t.count()
-			}
-		}
-
-		self.seed
-			.into_iter()
-			.map(|stone| walk::<T,TIMES>(Evolution::Single(stone),0))
-			.sum::<usize>()
+	/// Reads the stones from the first line of the input, and the
+	/// variant of the puzzle to play from the lines after it, if any
+	fn read(input:&str) -> Result<(Self,Option<usize>),InputError> {
+		let input = Input(input);
+		let line = input.first_line()?;
+		let variant = Variant::read(input.numbered_lines().skip(1))?;
+		let blinker = Self::try_from(line)?;
+		Ok((Self { rules: variant.rules, ..blinker }, variant.blinks))
 	}
 
-	fn collect<const TIMES:u8>(self) -> String {
-
-		fn walk<T:Evolve,const MAX:u8>(t:impl Iterator<Item=T>,i:u8) -> Vec<T> {
-			if i < MAX {
-				t.map(|s| walk::<T,MAX>(s.evolve(),i+1)).flatten().collect_vec()
-			} else {
-				t.collect_vec()
+	/// Counts the stones after blinking a number of times.
+	///
+	/// Order does not matter when counting, and stones with
+	/// the same number evolve the same way, so only how many
+	/// stones carry each number is tracked, blink after blink.
+	fn count(self, times:usize) -> Result<usize,AppError> {
+
+		let Self { seed, rules } = self;
+		let mut stones:HashMap<T,usize> = seed.into_iter().counts();
+
+		for blink in 1..=times {
+			let mut next = HashMap::with_capacity(stones.len());
+			for (stone,n) in stones {
+				for s in stone.evolve(&rules).ok_or_else(|| overflow(blink))? {
+					let count:&mut usize = next.entry(s).or_default();
+					*count = count.checked_add(n).ok_or_else(|| overflow(blink))?;
+				}
 			}
+			stones = next;
 		}
 
-		self.seed
-			.into_iter()
-			.flat_map(|stone| walk::<T,TIMES>(Evolution::Single(stone),0))
-			.map(|s| s.to_string())
-			.join(" ")
+		stones.values().try_fold(0usize, |total,&n| total.checked_add(n)).ok_or_else(|| overflow(times))
 	}
 }
 
-impl<T:Evolve> From<&str> for Blinker<T> {
-	fn from(value: &str) -> Self {
-		let seed = value.split(' ').map(|n| str::parse::<usize>(n).unwrap().into()).collect_vec();
-		Self { seed }
+impl<T:Evolve> TryFrom<Line<'_>> for Blinker<T> {
+	type Error = InputError;
+
+	fn try_from(line: Line) -> Result<Self,Self::Error> {
+		let seed = line.raw.split(' ')
+			// keep the offset of each number within the line
+			.scan(0,|offset,n| {
+				let at = *offset;
+				*offset += n.chars().count() + 1;
+				Some((at,n))
+			})
+			.map(|(at,n)| {
+				str::parse::<usize>(n)
+					.map(T::from)
+					.map_err(|e| line.error(at, format!("Expected a number, found '{n}' ({e})")))
+			})
+			.try_collect()?;
+		Ok(Self { seed, rules: Rules::default() })
 	}
 }
 
-fn solve_1(input: &str) -> String {
-	let line = Input(input).lines().next().unwrap();
-	let blinker:Blinker<Stone> = line.into();
-	blinker.count::<25>().to_string()
+/// Counts the stones after blinking as many times as the part asks,
+/// unless the input sets another amount
+fn solve(input: &str, blinks:usize) -> Result<String,AppError> {
+	let (blinker,times) = Blinker::<Stone>::read(input)?;
+	Ok(blinker.count(times.unwrap_or(blinks))?.to_string())
+}
+
+pub fn solve_1(input: &str) -> Result<String,AppError> {
+	solve(input, 25)
+}
+
+pub fn solve_2(input: &str) -> Result<String,AppError> {
+	solve(input, 75)
 }
 
 #[cfg(test)]
 mod test {
 
 	use super::*;
-	use aoc_driver::Part::*;
 
 	const INPUT_EXAMPLE:&str =
 		r###"
 		125 17
 		"###;
 
+	// Alternative to `Stone`, to check it against, keeping track
+	// of its digits. The length is provided when it's created
+	#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+	struct SizedStone(usize,u8);
+
+	impl TryFrom<&str> for SizedStone {
+		type Error = ();
+
+		fn try_from(input: &str) -> Result<Self, Self::Error> {
+			let len = u8::try_from(input.len()).map_err(|_|())?;
+			let val = str::parse(input).map_err(|_|())?;
+			Ok(SizedStone(val,len))
+		}
+	}
+
+	impl From<usize> for SizedStone {
+		fn from(value: usize) -> Self {
+			let len = 1 + if value >= 10 { value.ilog10() as u8 } else { 0 };
+			Self(value,len)
+		}
+	}
+
+	impl From<(usize,u8)> for SizedStone {
+		fn from((val,len): (usize,u8)) -> Self {
+			Self(val,len)
+		}
+	}
+
+	impl SizedStone {
+		#[inline]
+		pub fn try_split(&self, split:Split) -> Option<((usize,u8),usize)> {
+
+			let SizedStone(val,len) = self;
+
+			if let Split::Last(digits) = split {
+				return (u32::from(*len) > digits).then(|| {
+					let n = 10usize.pow(digits);
+					((val/n, len - digits as u8), val%n)
+				})
+			}
+
+			if split == Split::Halves && len % 2 == 0 {
+				let half = len/2;
+				let n = 10usize.pow(half as u32);
+				let upper = val/n;
+				let lower = val%n;
+				Some((
+					(upper, half),
+					// leading zeroes are eliminated!
+					// or it can be just 0
+					lower
+				))
+			} else {
+				None
+			}
+		}
+	}
+
+	impl Evolve for SizedStone {
+		fn evolve(&self, rules:&Rules) -> Option<Evolution<Self>> {
+			use Evolution::*;
+			let v = self.0;
+			if v == 0 {
+				Some(Single(Self::from(rules.zero)))
+			} else if let Some((fst,snd)) = self.try_split(rules.split) {
+				Some(Couple(fst.into(),snd.into()))
+			} else {
+				v.checked_mul(rules.multiplier).map(|v| Single(Self::from(v)))
+			}
+		}
+	}
+
+	impl Display for SizedStone {
+		fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+			write!(f,"{}",self.0)
+		}
+	}
+
+	impl<T:Evolve> Blinker<T> {
+
+		/// Lists the stones, in order, after blinking a number of times
+		fn collect(self, times:usize) -> Result<String,AppError> {
+
+			fn walk<T:Evolve>(stone:T,blink:usize,times:usize,rules:&Rules) -> Result<Vec<T>,AppError> {
+				if blink > times {
+					return Ok(vec![stone])
+				}
+				stone.evolve(rules).ok_or_else(|| super::overflow(blink))?
+					.map(|s| walk(s,blink+1,times,rules))
+					.flatten_ok()
+					.collect()
+			}
+
+			let Self { seed, rules } = self;
+
+			seed
+				.into_iter()
+				.map(|stone| walk(stone,1,times,&rules))
+				.flatten_ok()
+				.map_ok(|s| s.to_string())
+				.process_results(|mut stones| stones.join(" "))
+		}
+	}
+
 	#[test]
 	fn stone() {
-		assert!(matches!(Stone(  10).try_split() , Some(( 1,0) )));
-		assert!(matches!(Stone(  11).try_split() , Some(( 1,1) )));
-		assert!(matches!(Stone(  19).try_split() , Some(( 1,9) )));
-		assert!(matches!(Stone(1000).try_split() , Some((10,0) )));
-		assert!(matches!(Stone(   0).try_split() , None));
-		assert!(matches!(Stone(   1).try_split() , None));
-		assert!(matches!(Stone(   9).try_split() , None));
-		assert!(matches!(Stone( 111).try_split() , None));
+		let split = Split::Halves;
+		assert!(matches!(Stone(  10).try_split(split) , Some(( 1,0) )));
+		assert!(matches!(Stone(  11).try_split(split) , Some(( 1,1) )));
+		assert!(matches!(Stone(  19).try_split(split) , Some(( 1,9) )));
+		assert!(matches!(Stone(1000).try_split(split) , Some((10,0) )));
+		assert!(Stone(   0).try_split(split).is_none());
+		assert!(Stone(   1).try_split(split).is_none());
+		assert!(Stone(   9).try_split(split).is_none());
+		assert!(Stone( 111).try_split(split).is_none());
+
+		let split = Split::Last(2);
+		assert!(matches!(Stone( 123).try_split(split) , Some(( 1,23) )));
+		assert!(matches!(Stone( 100).try_split(split) , Some(( 1, 0) )));
+		assert!(Stone(  99).try_split(split).is_none());
+		assert!(matches!(SizedStone::from(123).try_split(split), Some(((1,1),23))));
+		assert!(SizedStone::from(99).try_split(split).is_none());
+
+		assert!(Stone(1000).try_split(Split::Never).is_none());
+	}
+
+	#[test]
+	fn rules() {
+
+		const INPUT:&str = r###"
+		125 17
+		# zero stones stay as they are
+		zero = 0
+		split = last 1
+		  multiply = 3   # single digits can't lose their last one
+		"###;
+
+		let (blinker,blinks) = Blinker::<Stone>::read(&dedent(INPUT)).unwrap();
+		let rules = blinker.rules;
+		assert_eq!(rules, Rules { zero: 0, split: Split::Last(1), multiplier: 3 });
+		assert_eq!(blinks, None);
+
+		let expected = "12 5 1 7";
+		let actual = blinker.collect(1).unwrap();
+		assert_eq!(actual, expected);
+
+		let (blinker,_) = Blinker::<Stone>::read(&dedent(INPUT)).unwrap();
+		let expected = "1 2 15 3 21";
+		let actual = blinker.collect(2).unwrap();
+		assert_eq!(actual, expected);
+
+		let (blinker,blinks) = Blinker::<Stone>::read("0").unwrap();
+		assert_eq!((blinker.rules,blinks), (Rules::default(),None));
+		let (_,blinks) = Blinker::<Stone>::read("0\nblinks = 6").unwrap();
+		assert_eq!(blinks, Some(6));
+
+		let Err(err) = Blinker::<Stone>::read("0\nzero = 1\nsplit = thirds") else {
+			panic!("Unknown split should be rejected")
+		};
+		assert_eq!((err.line,err.column), (3,9));
+
+		// Both kinds of stone follow the same rules
+		let input = dedent(INPUT_EXAMPLE);
+		let line = Input(&input).first_line().unwrap();
+		let rules = Rules { multiplier: 3, ..Rules::default() };
+		let blinker:Blinker<Stone> = line.try_into().unwrap();
+		let expected = Blinker { rules, ..blinker }.count(40).unwrap();
+		let blinker:Blinker<SizedStone> = line.try_into().unwrap();
+		let actual = Blinker { rules, ..blinker }.count(40).unwrap();
+		assert_eq!(actual, expected);
+	}
+
+	#[test]
+	fn overflow() {
+
+		// 2024^6 no longer fits
+		const INPUT:&str = "1\nsplit = never\nblinks = 6";
+
+		assert!(matches!(solve_1(INPUT), Err(AppError::Unsolvable(_))));
+		let (blinker,_) = Blinker::<SizedStone>::read(INPUT).unwrap();
+		assert!(matches!(blinker.collect(6), Err(AppError::Unsolvable(_))));
+
+		let (blinker,_) = Blinker::<Stone>::read(INPUT).unwrap();
+		assert_eq!(blinker.count(5).unwrap(), 1);
+	}
+
+	#[test]
+	fn bad_input() {
+
+		let Err(err) = Blinker::<Stone>::read("125 é 17") else {
+			panic!("Input should be rejected")
+		};
+		assert_eq!((err.line,err.column), (1,5));
+		assert!(err.reason.contains("'é'"));
 	}
 
 	#[test]
@@ -271,60 +520,77 @@ mod test {
 	#[test]
 	fn part_1_example() {
 
-		let line = Input(INPUT_EXAMPLE).lines().next().unwrap();
+		let input = dedent(INPUT_EXAMPLE);
+		let line = Input(&input).first_line().unwrap();
 
-		let blinker:Blinker<Stone> = line.into();
+		let blinker:Blinker<Stone> = line.try_into().unwrap();
 		let expected = "253000 1 7";
-		let actual = blinker.collect::<1>();
+		let actual = blinker.collect(1).unwrap();
 		assert_eq!(actual, expected);
 
-		let blinker:Blinker<Stone> = line.into();
+		let blinker:Blinker<Stone> = line.try_into().unwrap();
 		let expected = "253 0 2024 14168";
-		let actual = blinker.collect::<2>();
+		let actual = blinker.collect(2).unwrap();
 		assert_eq!(actual, expected);
 
-		let blinker:Blinker<Stone> = line.into();
+		let blinker:Blinker<Stone> = line.try_into().unwrap();
 		let expected = "512072 1 20 24 28676032";
-		let actual = blinker.collect::<3>();
+		let actual = blinker.collect(3).unwrap();
 		assert_eq!(actual, expected);
 
-		let blinker:Blinker<Stone> = line.into();
+		let blinker:Blinker<Stone> = line.try_into().unwrap();
 		let expected = "512 72 2024 2 0 2 4 2867 6032";
-		let actual = blinker.collect::<4>();
+		let actual = blinker.collect(4).unwrap();
 		assert_eq!(actual, expected);
 
-		let blinker:Blinker<Stone> = line.into();
+		let blinker:Blinker<Stone> = line.try_into().unwrap();
 		let expected = "1036288 7 2 20 24 4048 1 4048 8096 28 67 60 32";
-		let actual = blinker.collect::<5>();
+		let actual = blinker.collect(5).unwrap();
 		assert_eq!(actual, expected);
 
-		let blinker:Blinker<Stone> = line.into();
+		let blinker:Blinker<Stone> = line.try_into().unwrap();
 		let expected = "2097446912 14168 4048 2 0 2 4 40 48 2024 40 48 80 96 2 8 6 7 6 0 3 2";
-		let actual = blinker.collect::<6>();
+		let actual = blinker.collect(6).unwrap();
 		assert_eq!(actual, expected);
 
 		// Counts
 
-		let blinker:Blinker<Stone> = line.into();
+		let blinker:Blinker<Stone> = line.try_into().unwrap();
 		let expected = 22;
-		let actual = blinker.count::<6>();
+		let actual = blinker.count(6).unwrap();
 		assert_eq!(actual, expected);
 
-		let blinker:Blinker<Stone> = line.into();
+		let blinker:Blinker<Stone> = line.try_into().unwrap();
 		let expected = 55312;
-		let actual = blinker.count::<25>();
+		let actual = blinker.count(25).unwrap();
 		assert_eq!(actual, expected);
 
-		let blinker:Blinker<SizedStone> = line.into();
+		let blinker:Blinker<SizedStone> = line.try_into().unwrap();
 		let expected = 55312;
-		let actual = blinker.count::<25>();
+		let actual = blinker.count(25).unwrap();
+		assert_eq!(actual, expected);
+	}
+
+	#[test]
+	fn part_2_example() {
+
+		let input = dedent(INPUT_EXAMPLE);
+		let line = Input(&input).first_line().unwrap();
+
+		let blinker:Blinker<Stone> = line.try_into().unwrap();
+		let expected = 65601038650482;
+		let actual = blinker.count(75).unwrap();
+		assert_eq!(actual, expected);
+
+		let blinker:Blinker<SizedStone> = line.try_into().unwrap();
+		let actual = blinker.count(75).unwrap();
 		assert_eq!(actual, expected);
 	}
 
 	#[test]
 	fn submit()-> Result<(), AppError> {
-		try_submit(Day(11), solve_1, Part1)?;
-		// try_submit(Day(11), solve_2, Part2)?;
+		try_submit(Day(11), solve_1, 1)?;
+		try_submit(Day(11), solve_2, 2)?;
 		Ok(())
 	}
 }
@@ -10,64 +10,584 @@ mod day09;
 mod day10;
 mod day11;
 
-use std::fmt::Display;
+use std::{fmt::Display, io::BufRead};
 
-pub(self) use itertools::Itertools;
-pub(self) use types::*;
+use itertools::Itertools;
+use types::*;
 
-use aoc_driver::Part;
 use peg::{error::ParseError, str::LineCol};
 
 use super::*;
+use crate::answers::{Answers, Verdict};
 
-#[derive(Clone, Copy)]
-struct Day(usize);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Day(pub usize);
 
-impl Into<i32> for Day {
-	fn into(self) -> i32 {
-		self.0 as i32
+impl From<Day> for i32 {
+	fn from(day: Day) -> Self {
+		day.0 as i32
 	}
 }
 
 impl Display for Day {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		write!(f, "{}", self.0)
+		// honor width and alignment flags
+		Display::fmt(&self.0, f)
+	}
+}
+
+/// A non-empty line of input, along with its line number
+#[derive(Debug, Clone, Copy)]
+pub struct Line<'a> {
+	/// Line number, starting at 1
+	pub number: usize,
+	/// Line content, as found in the input, leading whitespace included
+	/// since it may be significant. Inline examples should be `dedent`ed.
+	pub raw: &'a str,
+}
+
+impl<'a> Line<'a> {
+
+	/// Builds an error pointing at an offset into the line
+	pub fn error(&self, offset:usize, reason:impl Into<String>) -> InputError {
+		InputError::new(self.number, offset + 1, self.raw, reason)
+	}
+
+	pub fn parse<T>(&self, parse:fn(&'a str) -> Result<T,ParseError<LineCol>>) -> Result<T,InputError> {
+		parse(self.raw).map_err(|e| InputError::from_peg(self.number, self.raw, &e))
 	}
 }
 
-struct Input<'a>(&'a str);
+pub struct Input<'a>(pub &'a str);
 impl<'a> Input<'a> {
 
+	/// Return an iterator of non-empty lines in the input,
+	/// keeping track of where they were found
+	fn numbered_lines(&self) -> impl Clone + Iterator<Item=Line<'a>> + use<'a> {
+		self.0.lines()
+			.enumerate()
+			.map(|(n,raw)| Line { number: n+1, raw })
+			.filter(|l| !l.raw.is_empty())
+	}
+
 	// Return an iterator of lines in the input
 	fn lines(&self) -> impl Clone + Iterator<Item=&'a str> + use<'a> {
-		// clean up input comming from inline examples
-		self.0.lines().map(str::trim_start).filter(|l| !l.is_empty())
+		self.numbered_lines().map(|l| l.raw)
 	}
 
 	/// Given a parser function, returns an iterator of parsed items of type T.
-	/// Panics if parsing fails.
-	fn parse_iter<T>(&self, parse:fn(&'a str) -> Result<T,ParseError<LineCol>>)-> impl Iterator<Item=T> + use<'a,T> {
+	/// Items that fail to parse are reported with their location.
+	fn parse_iter<T>(&self, parse:fn(&'a str) -> Result<T,ParseError<LineCol>>)-> impl Iterator<Item=Result<T,AppError>> + use<'a,T> {
 
 		// return a lazy parsing iterator
-		self.lines().map(move |l| {
-			parse(l)
-				.inspect_err(|e| eprintln!("Failed parsing {l}: {e}"))
-				.expect("Parser should not fail")
+		self.numbered_lines().map(move |l| Ok(l.parse(parse)?))
+	}
+
+	/// Splits the input into runs of non-empty lines, separated by blank lines
+	fn sections(&self) -> Vec<Section<'a>> {
+		self.0.lines()
+			.enumerate()
+			.map(|(n,raw)| Line { number: n+1, raw })
+			.chunk_by(|l| l.raw.is_empty())
+			.into_iter()
+			.filter(|(blank,_)| !blank)
+			.map(|(_,lines)| Section(lines.collect()))
+			.collect()
+	}
+
+	/// Returns the first non-empty line, or an error
+	/// when the input does not have any
+	fn first_line(&self) -> Result<Line<'a>,InputError> {
+		self.numbered_lines().next()
+			.ok_or_else(|| InputError::new(1, 1, "", "Input is empty"))
+	}
+
+	/// Returns the last non-empty line, or an error
+	/// when the input does not have any
+	fn last_line(&self) -> Result<Line<'a>,InputError> {
+		self.numbered_lines().last()
+			.ok_or_else(|| InputError::new(1, 1, "", "Input is empty"))
+	}
+
+	/// Points an error located by row and column within the
+	/// non-empty lines at its actual place in the input
+	fn locate(&self, err:InputError) -> InputError {
+		match err.line.checked_sub(1).and_then(|n| self.numbered_lines().nth(n)) {
+			Some(line) => line.error(err.column - 1, err.reason),
+			None => err
+		}
+	}
+
+	/// Reads a map, one cell per byte, see `Map::parse`
+	fn map<T,E:Display>(&self, decode:impl FnMut(u8) -> Result<T,E>) -> Result<Map<T>,InputError> {
+		Map::parse(self.lines(), decode).map_err(|e| self.locate(e))
+	}
+
+}
+
+/// Removes the indentation common to the lines of an inline example,
+/// so it reads as an input file. Lines with only whitespace keep
+/// whatever is left past that indentation.
+#[cfg(test)]
+pub fn dedent(text:&str) -> String {
+
+	// longest whitespace every non-blank line starts with
+	let indent = text.lines()
+		.filter(|l| !l.trim().is_empty())
+		.map(|l| &l[..l.len() - l.trim_start().len()])
+		.reduce(|a,b| &a[..a.bytes().zip(b.bytes()).take_while(|(x,y)| x == y).count()])
+		.unwrap_or("");
+
+	// split, rather than lines(), keeps a final line break
+	text.split('\n')
+		.map(|l| l.strip_prefix(indent).unwrap_or(""))
+		.join("\n")
+}
+
+/// Consecutive non-empty lines of an input, see `Input::sections`
+#[derive(Debug, Clone)]
+pub struct Section<'a>(Vec<Line<'a>>);
+impl<'a> Section<'a> {
+
+	fn numbered_lines(&self) -> impl Clone + Iterator<Item=Line<'a>> + use<'a> {
+		self.0.clone().into_iter()
+	}
+
+	/// Return an iterator of the lines in the section
+	fn lines(&self) -> impl Clone + Iterator<Item=&'a str> + use<'a> {
+		self.numbered_lines().map(|l| l.raw)
+	}
+
+	/// Given a parser function, returns an iterator of parsed items of type T,
+	/// as `Input::parse_iter` does
+	fn parse_iter<T>(&self, parse:fn(&'a str) -> Result<T,ParseError<LineCol>>)-> impl Iterator<Item=Result<T,AppError>> + use<'a,T> {
+		self.numbered_lines().map(move |l| Ok(l.parse(parse)?))
+	}
+
+	/// Returns the last line, as sections are never empty
+	fn last_line(&self) -> Line<'a> {
+		self.0[self.0.len()-1]
+	}
+
+	/// Points an error located by row and column within the
+	/// section at its actual place in the input
+	fn locate(&self, err:InputError) -> InputError {
+		match err.line.checked_sub(1).and_then(|n| self.0.get(n)) {
+			Some(line) => line.error(err.column - 1, err.reason),
+			None => err
+		}
+	}
+
+	/// Reads a map, along with its marker cells, see `Map::parse_marked`
+	fn marked_map<T,E:Display>(&self, markers:&[u8], decode:impl FnMut(u8) -> Result<T,E>) -> Result<(Map<T>,Markers),InputError> {
+		Map::parse_marked(self.lines(), markers, decode).map_err(|e| self.locate(e))
+	}
+}
+
+/// Lines of an input, either held in memory by an `Input`, or read
+/// as they come by a `Stream`, so line oriented days can take both
+pub trait Source {
+
+	type Line: AsRef<str>;
+
+	/// Return an iterator of non-empty lines,
+	/// stopping at the first one that can't be read
+	fn lines(self) -> impl Iterator<Item=Result<Self::Line,AppError>>;
+
+	/// Given a parser function, returns an iterator of parsed items of type T,
+	/// as `Input::parse_iter` does
+	fn parse_iter<T>(self, parse:for<'b> fn(&'b str) -> Result<T,ParseError<LineCol>>) -> impl Iterator<Item=Result<T,AppError>>;
+}
+
+impl<'a> Source for &Input<'a> {
+
+	type Line = &'a str;
+
+	fn lines(self) -> impl Iterator<Item=Result<&'a str,AppError>> {
+		Input::lines(self).map(Ok)
+	}
+
+	fn parse_iter<T>(self, parse:for<'b> fn(&'b str) -> Result<T,ParseError<LineCol>>) -> impl Iterator<Item=Result<T,AppError>> {
+		Input::parse_iter(self, parse)
+	}
+}
+
+/// Input read line by line from a buffered reader, for
+/// inputs too large to be loaded in memory at once
+pub struct Stream<R>(pub R);
+
+impl<R:BufRead> Source for Stream<R> {
+
+	type Line = String;
+
+	fn lines(self) -> impl Iterator<Item=Result<String,AppError>> {
+		self.numbered_lines().map_ok(|(_,raw)| raw)
+	}
+
+	fn parse_iter<T>(self, parse:for<'b> fn(&'b str) -> Result<T,ParseError<LineCol>>) -> impl Iterator<Item=Result<T,AppError>> {
+		self.numbered_lines().map(move |line| {
+			let (number,raw) = line?;
+			Ok(Line { number, raw: &raw }.parse(parse)?)
 		})
 	}
 }
 
-fn try_submit(day:Day, solver:fn(&str)->String, part:Part)->Result<(),AppError> {
+impl<R:BufRead> Stream<R> {
+
+	fn numbered_lines(self) -> impl Iterator<Item=Result<(usize,String),AppError>> {
+		self.0.lines()
+			.enumerate()
+			.map(|(n,raw)| raw
+				.map(|raw| (n+1,raw))
+				.map_err(|e| AppError::MissingInput(format!("Can't read line {}: {e}", n+1)))
+			)
+			.filter_ok(|(_,raw)| !raw.is_empty())
+			// don't go on reading past an error
+			.take_while_inclusive(Result::is_ok)
+	}
+}
+
+/// A reader going over the same text several times, to
+/// stream large inputs without holding them in memory
+#[cfg(test)]
+pub fn repeated(text:&str, times:usize) -> impl BufRead + use<> {
+
+	struct Repeat { text:Vec<u8>, at:usize, left:usize }
+
+	impl std::io::Read for Repeat {
+		fn read(&mut self, buf:&mut [u8]) -> std::io::Result<usize> {
+			if self.at == self.text.len() && self.left > 0 {
+				self.at = 0;
+				self.left -= 1;
+			}
+			let rest = if self.left > 0 { &self.text[self.at..] } else { &[] };
+			let n = rest.len().min(buf.len());
+			buf[..n].copy_from_slice(&rest[..n]);
+			self.at += n;
+			Ok(n)
+		}
+	}
+
+	std::io::BufReader::new(Repeat { text: text.as_bytes().to_vec(), at: 0, left: times })
+}
+
+/// Returns true when solutions should be posted to the website,
+/// rather than verified against locally recorded answers
+#[cfg(test)]
+fn online() -> bool {
+	std::env::var("AOC_ONLINE").is_ok_and(|v| !v.is_empty())
+}
+
+/// Verifies a solution against the recorded answers,
+/// or posts it when explicitly asked with `AOC_ONLINE`.
+/// An unknown outcome is reported, but is not an error.
+#[cfg(test)]
+pub fn try_submit(day:Day, solver:Solver, part:u8)->Result<(),AppError> {
+
+	if online() {
+		return post(day, solver, part)
+	}
+
+	match verify(day, solver, part)? {
+		Verdict::Fail { expected, actual } => {
+			let msg = format!("Solution for day {day} part {part} is {actual}, expected {expected}");
+			Err(AppError::IncorrectSolution(msg))
+		}
+		Verdict::Error(reason) => {
+			let msg = format!("Solver for day {day} part {part} failed: {reason}");
+			Err(AppError::IncorrectSolution(msg))
+		}
+		verdict => {
+			eprintln!("Day {day:>2} part {part}: {verdict}");
+			Ok(())
+		}
+	}
+}
+
+/// Checks a solver output against the answer recorded in
+/// `answers/<day>.toml`. Never goes to the network.
+/// A solver failing is a verdict too, not an error.
+pub fn verify(day:Day, solver:Solver, part:u8) -> Result<Verdict,AppError> {
 
+	let answers = Answers::load(day)?;
+
+	if answers.get(part).is_none() {
+		return Ok(Verdict::Unknown("no recorded answer".into()))
+	}
+
+	let Ok(input) = std::fs::read_to_string(format!("inputs/{day}.txt")) else {
+		return Ok(Verdict::Unknown("no local input".into()))
+	};
+
+	Ok(match solver(&input) {
+		Ok(actual) => answers.check(part, actual),
+		Err(e) => Verdict::Error(e.to_string()),
+	})
+}
+
+/// Posts a solver output to the website, recording it
+/// in `answers/<day>.toml` once it's been accepted
+pub fn post(day:Day, solver:Solver, part:u8)->Result<(),AppError> {
+
+	let input = input_for(day)?;
 	let cookie: String = cookie()?;
 
-	aoc_driver::calculate_and_post(
-		&cookie, YEAR, day, part,
-		Some(format!("inputs/{day}.txt")),
+	std::fs::create_dir_all("cache").map_err(|e| {
+		let msg = format!("Can't create cache folder: {e}");
+		AppError::BadConfiguration(msg)
+	})?;
+
+	let answer = solver(&input)?;
+
+	aoc_driver::post_answer(
+		&cookie, YEAR, day.into(), part.into(),
 		Some(format!("cache/{day}.json")),
-		solver
+		&answer
 	).map_err(|e| {
 		let msg = format!("Solution for day {day} rejected: {e:?}");
 		AppError::IncorrectSolution(msg)
-	})
+	})?;
+
+	Answers::record(day, part, &answer)
+}
+
+pub type Solver = fn(&str) -> Result<String,AppError>;
+
+/// A solver reading its input line by line, see `Stream`
+pub type StreamSolver = fn(&mut dyn BufRead) -> Result<String,AppError>;
+
+/// A solver for one part of a day's puzzle
+#[derive(Clone, Copy)]
+pub struct Solution {
+	pub day: Day,
+	pub part: u8,
+	pub solver: Solver,
+	/// The same solver, reading its input line by line,
+	/// for line oriented days
+	pub stream: Option<StreamSolver>,
+}
+
+const fn solution(day:usize, part:u8, solver:Solver) -> Solution {
+	Solution { day: Day(day), part, solver, stream: None }
+}
+
+/// A solution for a line oriented day, whose solver takes any
+/// `Source`, registered for whole inputs and streams alike
+macro_rules! lines {
+	($day:literal, $part:literal, $solve:path) => {
+		Solution {
+			day: Day($day),
+			part: $part,
+			solver: |input| $solve(&Input(input)),
+			stream: Some(|reader| $solve(Stream(reader))),
+		}
+	};
+}
+
+/// Registry of every implemented solver, sorted by day and part
+const SOLUTIONS:&[Solution] = &[
+	lines!( 1, 1, day01::solve_1),
+	lines!( 1, 2, day01::solve_2),
+	lines!( 2, 1, day02::solve_1),
+	lines!( 2, 2, day02::solve_2),
+	lines!( 3, 1, day03::solve_1),
+	lines!( 3, 2, day03::solve_2),
+	solution( 4, 1, day04::solve_1),
+	solution( 4, 2, day04::solve_2),
+	solution( 5, 1, day05::solve_1),
+	solution( 5, 2, day05::solve_2),
+	solution( 6, 1, day06::solve_1),
+	solution( 6, 2, day06::solve_2),
+	lines!( 7, 1, day07::solve_1),
+	lines!( 7, 2, day07::solve_2),
+	solution( 8, 1, day08::solve_1),
+	solution( 8, 2, day08::solve_2),
+	solution( 9, 1, day09::solve_1),
+	solution( 9, 2, day09::solve_2),
+	solution(10, 1, day10::solve_1),
+	solution(10, 2, day10::solve_2),
+	solution(11, 1, day11::solve_1),
+	solution(11, 2, day11::solve_2),
+];
+
+/// Returns an iterator over every registered solution
+pub fn solutions() -> impl Clone + Iterator<Item=Solution> {
+	SOLUTIONS.iter().copied()
+}
+
+/// Returns the solver for the given day and part,
+/// or `None` when it has not been implemented (yet)
+pub fn solver(day:Day, part:u8) -> Option<Solver> {
+	solutions()
+		.find(|s| s.day == day && s.part == part)
+		.map(|s| s.solver)
+}
+
+/// Returns a solver streaming its input for the given day and
+/// part, or `None` when the day needs its whole input at once
+pub fn stream_solver(day:Day, part:u8) -> Option<StreamSolver> {
+	solutions()
+		.find(|s| s.day == day && s.part == part)
+		.and_then(|s| s.stream)
+}
+
+/// Draws the progress of a solver on a day's input, calling
+/// back with a frame for each step of a part's solution
+pub type Animation = fn(&str, u8, &mut dyn FnMut(String)) -> Result<(),AppError>;
+
+/// Returns the animation for the given day,
+/// or `None` when the day has none
+pub fn animation(day:Day) -> Option<Animation> {
+	match day {
+		Day(6) => Some(day06::animate),
+		_ => None
+	}
+}
+
+/// Returns the days having at least one part solved,
+/// along with which of their parts are implemented
+pub fn implemented() -> impl Iterator<Item=(Day,[bool;2])> {
+	solutions()
+		.chunk_by(|s| s.day)
+		.into_iter()
+		.map(|(day,parts)| {
+			let mut done = [false;2];
+			parts.for_each(|s| done[s.part as usize - 1] = true);
+			(day,done)
+		})
+		.collect_vec()
+		.into_iter()
+}
+
+/// Reads the puzzle input for a day from `inputs/<day>.txt`,
+/// downloading it first when the file is not there yet
+pub fn input_for(day:Day) -> Result<String,AppError> {
+
+	let path = format!("inputs/{day}.txt");
+
+	if let Ok(input) = std::fs::read_to_string(&path) {
+		return Ok(input)
+	}
+
+	let cookie: String = cookie()?;
+
+	std::fs::create_dir_all("inputs")
+		.map_err(|e| AppError::MissingInput(format!("Can't create inputs folder: {e}")))?;
+
+	aoc_driver::get_input_or_file(&cookie, YEAR, day, &path)
+		.map_err(|e| AppError::MissingInput(format!("Can't fetch input for day {day}: {e:?}")))
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn registry() {
+
+		// sorted by day and part, without duplicates
+		let keys = solutions().map(|s| (s.day,s.part)).collect_vec();
+		assert!(keys.iter().tuple_windows().all(|(a,b)| a < b));
+		assert!(keys.iter().all(|(_,part)| (1..=2).contains(part)));
+
+		assert!(solver(Day(1),1).is_some());
+		assert!(solver(Day(1),3).is_none());
+		assert!(solver(Day(25),1).is_none());
+
+		let (day,parts) = implemented().next().unwrap();
+		assert_eq!(day, Day(1));
+		assert_eq!(parts, [true,true]);
+
+		assert!(stream_solver(Day(1),1).is_some());
+		assert!(stream_solver(Day(4),1).is_none());
+	}
+
+	#[test]
+	fn streamed() {
+
+		// lines from several days, so that each solver
+		// meets some it accepts, and some it rejects
+		let inputs = [
+			"3   4\n4   3\n2   5\n",
+			"7 6 4 2 1\n1 3 2 4 5\n",
+			"xmul(2,4)don't()_mul(5,5)\n",
+			"190: 10 19\n3267: 81 40 27\n",
+		];
+
+		// streamed inputs get the same answers, or errors, as whole ones
+		for Solution { day, part, solver, stream } in solutions() {
+			let Some(stream) = stream else { continue };
+			for input in inputs {
+				let expected = solver(&input.repeat(100)).map_err(|e| e.to_string());
+				let actual = stream(&mut repeated(input, 100)).map_err(|e| e.to_string());
+				assert_eq!(actual, expected, "day {day} part {part} on {input:?}");
+			}
+		}
+	}
+
+	#[test]
+	fn sections() {
+
+		let input = super::dedent(r###"
+			a
+			b
+
+			c
+
+			d
+
+		"###);
+
+		let sections = Input(&input).sections();
+		let actual = sections.iter().map(|s| s.lines().collect_vec()).collect_vec();
+		assert_eq!(actual, vec![vec!["a","b"],vec!["c"],vec!["d"]]);
+
+		// lines keep their place in the whole input
+		let numbers = sections.iter().flat_map(|s| s.numbered_lines().map(|l| l.number)).collect_vec();
+		assert_eq!(numbers, vec![2,3,5,7]);
+
+		assert!(Input("\n\n").sections().is_empty());
+
+		// leading whitespace is part of real inputs
+		assert_eq!(Input(" a\n\n  b").lines().collect_vec(), vec![" a","  b"]);
+	}
+
+	#[test]
+	fn dedent() {
+
+		let actual = super::dedent("\n\t\t  a\n\t\t\n\t\t\t b\n\t");
+		assert_eq!(actual, "\n  a\n\n\t b\n");
+
+		// rows of whitespace are kept, past the common indent
+		assert_eq!(super::dedent("\n\t\ta\n\t\t  \n\t\tb\n\t"), "\na\n  \nb\n");
+		assert_eq!(Input(&super::dedent("\t\t.\n\t\t \n")).lines().collect_vec(), vec!["."," "]);
+
+		// leading spaces of real inputs are kept
+		assert_eq!(super::dedent(" a\nb\n"), " a\nb\n");
+	}
+
+	#[test]
+	fn stream() {
+
+		peg::parser!{
+			grammar number() for str {
+				pub rule value() -> u32 = n:$(['0'..='9']+) {? n.parse().or(Err("u32")) }
+			}
+		}
+
+		let text = "1\n\n2\nx3\n4\n";
+
+		let actual = Stream(text.as_bytes()).lines().try_collect::<_,Vec<_>,_>().unwrap();
+		assert_eq!(actual, Input(text).lines().collect_vec());
+
+		let actual = Stream(text.as_bytes()).parse_iter(number::value).collect_vec();
+		assert!(matches!(actual[..2], [Ok(1),Ok(2)]));
+		let Err(AppError::BadInput(err)) = &actual[2] else { panic!("Expected an input error") };
+		assert_eq!((err.line,err.column), (4,1));
+
+		// the same text, many times over
+		let actual = Stream(repeated("1\n2\n", 1000)).parse_iter(number::value).sum::<Result<u32,_>>();
+		assert!(matches!(actual, Ok(3000)));
+	}
 }
@@ -0,0 +1,95 @@
+use std::fmt::Display;
+
+use peg::{error::ParseError, str::LineCol};
+
+use super::*;
+
+#[allow(unused)]
+#[derive(Debug)]
+pub enum AppError {
+	BadConfiguration(String),
+	BadUsage(String),
+	BadInput(InputError),
+	MissingInput(String),
+	MissingSolver(String),
+	Unsolvable(String),
+	IncorrectSolution(String),
+	Regression(String)
+}
+
+impl Display for AppError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		use AppError::*;
+		match self {
+			BadConfiguration(msg)  => write!(f, "Configuration error: {msg}"),
+			BadUsage(msg)          => write!(f, "{msg}\n\n{}", cli::USAGE),
+			BadInput(err)          => write!(f, "Input error {err}"),
+			MissingInput(msg)      => write!(f, "Input error: {msg}"),
+			MissingSolver(msg)     => write!(f, "Solver error: {msg}"),
+			Unsolvable(msg)        => write!(f, "Unsolvable input: {msg}"),
+			IncorrectSolution(msg) => write!(f, "Submission error: {msg}"),
+			Regression(msg)        => write!(f, "Benchmark error: {msg}"),
+		}
+	}
+}
+
+impl From<InputError> for AppError {
+	fn from(err: InputError) -> Self {
+		AppError::BadInput(err)
+	}
+}
+
+/// Describes where, and why, some input could not be understood
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputError {
+	/// Line number, starting at 1
+	pub line: usize,
+	/// Column number, starting at 1
+	pub column: usize,
+	/// The offending line
+	pub text: String,
+	pub reason: String,
+}
+
+impl InputError {
+
+	pub fn new(line:usize, column:usize, text:&str, reason:impl Into<String>) -> Self {
+		InputError { line, column, text: text.into(), reason: reason.into() }
+	}
+
+	/// Builds an error from a failed `peg` parse of a line
+	pub fn from_peg(line:usize, text:&str, err:&ParseError<LineCol>) -> Self {
+		Self::new(line, err.location.column, text, format!("expected {}", err.expected))
+	}
+}
+
+impl Display for InputError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let InputError { line, column, text, reason } = self;
+		let gutter = line.to_string().len();
+		writeln!(f, "at line {line}, column {column}: {reason}")?;
+		// keep tabs, so the marker lines up with the offending text
+		let pad:String = text.chars().take(column-1).map(|c| if c == '\t' { c } else { ' ' }).collect();
+		writeln!(f, "{line} | {text}")?;
+		write!(f, "{:gutter$} | {pad}^", "")
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn input_error() {
+
+		let err = InputError::new(12, 5, "1 2 x 4", "expected digit");
+
+		let expected = "\
+at line 12, column 5: expected digit
+12 | 1 2 x 4
+   |     ^";
+
+		assert_eq!(err.to_string(), expected);
+	}
+}
@@ -0,0 +1,112 @@
+//! Tokens shared by line oriented puzzle inputs.
+//!
+//! `peg` grammars can't call each other's rules, so each
+//! public rule here matches a whole line, and solvers pass
+//! it to `Input::parse_iter` with its arguments, as in
+//! `|line| tokens::list(line, ',')`
+
+use std::str::FromStr;
+
+use crate::types::V2;
+
+peg::parser!{
+
+	pub grammar tokens() for str {
+
+		rule _ = quiet!{[' ' | '\t']}
+
+		rule digits() -> &'input str
+			= quiet!{ $(['0'..='9']+) } / expected!("digits")
+
+		/// A separator, with optional whitespace around it,
+		/// or any run of whitespace when it's a space
+		rule separator(sep:char)
+			= quiet!{ _* [c if c == sep] _* / _+ {? if sep == ' ' { Ok(()) } else { Err("separator") } } }
+			/ expected!("separator")
+
+		/// An unsigned integer, of any type it parses into
+		pub rule unsigned<T:FromStr>() -> T
+			= n:digits() {? n.parse().or(Err("unsigned integer within range")) }
+
+		/// An integer, with an optional minus sign, of any type it parses into
+		pub rule signed<T:FromStr>() -> T
+			= n:$("-"? digits()) {? n.parse().or(Err("integer within range")) }
+
+		/// One or more unsigned integers, such as `1,2,3`
+		pub rule list<T:FromStr>(sep:char) -> Vec<T>
+			= unsigned() ++ separator(sep)
+
+		/// Exactly two unsigned integers, such as `47|53`
+		pub rule pair<T:FromStr>(sep:char) -> (T,T)
+			= a:unsigned() separator(sep) b:unsigned() { (a,b) }
+
+		/// Coordinates, such as `-3,4`
+		pub rule coordinates() -> V2
+			= x:signed() "," y:signed() { V2{x,y} }
+
+		rule key() -> &'input str
+			= quiet!{ $(['a'..='z' | 'A'..='Z' | '0'..='9' | '_']+) } / expected!("key")
+
+		/// A key, then a value after a colon, such as `Button A: X+94, Y+34`
+		pub rule key_value() -> (&'input str, &'input str)
+			= k:$(key() ++ " ") _* ":" _* v:$([_]*) { (k,v) }
+
+		/// An unsigned integer, then a list after a colon, such as `190: 10 19`
+		pub rule labelled_list<T:FromStr>(sep:char) -> (T,Vec<T>)
+			= k:unsigned() _* ":" _* v:list(sep) { (k,v) }
+
+		/// Named coordinates separated by whitespace, such as `p=1,2 v=-3,4`
+		pub rule vectors() -> Vec<(&'input str, V2)>
+			= (k:key() "=" v:coordinates() { (k,v) }) ++ (_+)
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use pretty_assertions::assert_eq;
+	use super::*;
+
+	#[test]
+	fn numbers() {
+
+		assert_eq!(tokens::unsigned::<u64>("123456789012"), Ok(123456789012));
+		assert_eq!(tokens::unsigned::<u8>("255"), Ok(255));
+		assert_eq!(tokens::signed::<i64>("-42"), Ok(-42));
+		assert_eq!(tokens::list("1,22,333", ','), Ok(vec![1,22,333]));
+		assert_eq!(tokens::list("1 , 2", ','), Ok(vec![1,2]));
+		assert_eq!(tokens::list("7 6  4", ' '), Ok(vec![7,6,4]));
+		assert_eq!(tokens::pair("47|53", '|'), Ok((47,53)));
+		assert_eq!(tokens::pair("3   4", ' '), Ok((3,4)));
+		assert_eq!(tokens::list::<u8>("1,255", ','), Ok(vec![1,255]));
+		assert_eq!(tokens::pair::<i64>("3,4", ','), Ok((3,4)));
+		assert_eq!(tokens::coordinates("-3,4"), Ok(V2{x:-3,y:4}));
+
+		// consistent error messages, pointing at the offending column
+		let err = tokens::list::<usize>("1,x", ',').unwrap_err();
+		assert_eq!((err.location.column, err.expected.to_string()), (3, "digits".to_string()));
+		let err = tokens::unsigned::<u8>("256").unwrap_err();
+		assert_eq!(err.expected.to_string(), "unsigned integer within range");
+		let err = tokens::signed::<i32>("-99999999999").unwrap_err();
+		assert_eq!(err.expected.to_string(), "integer within range");
+		let err = tokens::list::<u8>("1,256", ',').unwrap_err();
+		assert_eq!(err.expected.to_string(), "unsigned integer within range");
+		let err = tokens::pair::<usize>("34", ' ').unwrap_err();
+		assert_eq!((err.location.column, err.expected.to_string()), (3, "separator".to_string()));
+		let err = tokens::pair::<usize>("3 4", ',').unwrap_err();
+		assert_eq!((err.location.column, err.expected.to_string()), (2, "separator".to_string()));
+	}
+
+	#[test]
+	fn lines() {
+
+		assert_eq!(tokens::key_value("Button A: X+94, Y+34"), Ok(("Button A","X+94, Y+34")));
+		assert_eq!(tokens::labelled_list("190: 10 19", ' '), Ok((190,vec![10,19])));
+
+		let actual = tokens::vectors("p=0,4 v=3,-3");
+		assert_eq!(actual, Ok(vec![("p",V2{x:0,y:4}),("v",V2{x:3,y:-3})]));
+
+		let err = tokens::vectors("p=0,4 v=3").unwrap_err();
+		assert_eq!(err.location.column, 10);
+	}
+}
@@ -1,24 +1,32 @@
 #![warn(rust_2024_incompatible_pat)]
 
 use std::process::ExitCode;
-use aoc_driver::aoc_magic;
 
 pub use std::convert::identity;
 
-#[cfg(test)]
+mod answers;
+mod bench;
+mod cli;
 mod days;
-
-#[cfg(test)]
+mod error;
+mod records;
+
+// General purpose toolkit: not everything
+// is used by the solvers at any given time
+#[allow(dead_code)]
+mod grammar;
+#[allow(dead_code)]
+mod search;
+#[allow(dead_code)]
 mod types;
 
+pub use error::*;
+
 pub const YEAR:i32 = 2024;
 
-#[allow(unused)]
-#[derive(Debug)]
-pub enum AppError {
-	BadConfiguration(String),
-	IncorrectSolution(String)
-}
+// counts the allocations for `bench`, at the cost of a relaxed atomic add each
+#[global_allocator]
+static ALLOCATOR:bench::Counting = bench::Counting;
 
 pub fn cookie() -> Result<String,AppError> {
 	std::env::var("COOKIE")
@@ -30,13 +38,13 @@ pub fn cookie() -> Result<String,AppError> {
 
 fn main() -> ExitCode {
 
-	let cookie = cookie().unwrap();
-
-	// Tests Creates `cache/` and `inputs/`
-	let _ = aoc_magic!(&cookie, 2024:1:1, |_| "");
-
-	eprintln!("Project configured.");
-	eprintln!("Run `cargo test` to build and submit solutions");
+	let args = std::env::args().skip(1);
 
-	ExitCode::FAILURE
+	match cli::Command::parse(args).and_then(cli::Command::execute) {
+		Ok(()) => ExitCode::SUCCESS,
+		Err(e) => {
+			eprintln!("{e}");
+			ExitCode::FAILURE
+		}
+	}
 }
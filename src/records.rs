@@ -0,0 +1,61 @@
+//! Small files of records kept alongside the sources, one per line,
+//! such as the recorded answers and the bench baseline
+
+use std::collections::BTreeMap;
+
+use peg::{error::ParseError, str::LineCol};
+
+use super::*;
+
+/// Reads a file of records with the given parser. A missing file
+/// has no records yet, but one that can't be read is an error.
+pub fn load_records<T:Default>(path:&str, parse:fn(&str) -> Result<T,String>) -> Result<T,AppError> {
+	match std::fs::read_to_string(path) {
+		Ok(content) => parse(&content).map_err(|e| {
+			AppError::BadConfiguration(format!("{path}: {e}"))
+		}),
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+		Err(e) => Err(AppError::BadConfiguration(format!("Can't read {path}: {e}")))
+	}
+}
+
+/// Collects the records found on each line by `entry`, which matches
+/// blank and comment lines too, returning `None` for them
+pub fn parse_records<'a,K:Ord,V>(content:&'a str, entry:impl Fn(&'a str) -> Result<Option<(K,V)>,ParseError<LineCol>>) -> Result<BTreeMap<K,V>,String> {
+	content.lines()
+		.enumerate()
+		.filter_map(|(n,line)| {
+			entry(line)
+				.map_err(|e| format!("line {}: {e}", n+1))
+				.transpose()
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	peg::parser!{
+		grammar pairs() for str {
+			pub rule entry() -> Option<(char,char)>
+				= a:[_] "=" b:[_] { Some((a,b)) }
+				/ "#" [_]* { None }
+		}
+	}
+
+	#[test]
+	fn records() {
+
+		let records = parse_records("a=1\n# note\nb=2", pairs::entry).unwrap();
+		assert_eq!(records, BTreeMap::from([('a','1'),('b','2')]));
+
+		let err = parse_records("a=1\nb", pairs::entry).unwrap_err();
+		assert!(err.starts_with("line 2: "));
+
+		let missing = load_records("records/none.txt", |_| Ok(vec![1])).unwrap();
+		assert!(missing.is_empty());
+		assert!(matches!(load_records::<Vec<u8>>("src", |_| Ok(vec![])), Err(AppError::BadConfiguration(_))));
+	}
+}
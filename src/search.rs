@@ -0,0 +1,354 @@
+//! Graph search primitives, generic over the type of state
+//! and over a function producing the successors of a state.
+//!
+//! Every search keeps, for each visited state, all the predecessors
+//! reaching it at the best known cost, so that not only a shortest
+//! path, but every shortest path can be rebuilt afterwards.
+
+use std::{cmp::Ordering, collections::{BinaryHeap, HashMap, HashSet, VecDeque}, hash::Hash};
+
+use crate::types::*;
+
+/// Best known cost to reach a state, and the states preceding it
+struct Visit<S> {
+	cost: usize,
+	parents: Vec<S>,
+}
+
+/// Outcome of a successful search
+pub struct Solution<S> {
+	/// Cost of the shortest paths
+	pub cost: usize,
+	/// Goal states reached at that cost
+	pub goals: Vec<S>,
+	visits: HashMap<S,Visit<S>>,
+}
+
+impl<S:Clone+Eq+Hash> Solution<S> {
+
+	/// One of the shortest paths, from the start to a goal (both included)
+	pub fn path(&self) -> Vec<S> {
+		let mut state = self.goals[0].clone();
+		let mut path = vec![state.clone()];
+		while let Some(parent) = self.visits[&state].parents.first() {
+			state = parent.clone();
+			path.push(state.clone());
+		}
+		path.reverse();
+		path
+	}
+
+	/// Every shortest path, from the start to any of the goals
+	pub fn paths(&self) -> impl Iterator<Item=Vec<S>> + '_ {
+
+		// Depth first walk backwards from the goals,
+		// with partial paths stored in reverse order
+		let mut pending:Vec<Vec<S>> = self.goals.iter().map(|g| vec![g.clone()]).collect();
+
+		std::iter::from_fn(move || {
+			while let Some(partial) = pending.pop() {
+				let last = partial.last().unwrap();
+				let parents = &self.visits[last].parents;
+				if parents.is_empty() {
+					let mut path = partial;
+					path.reverse();
+					return Some(path)
+				}
+				for parent in parents {
+					let mut longer = partial.clone();
+					longer.push(parent.clone());
+					pending.push(longer);
+				}
+			}
+			None
+		})
+	}
+
+	/// States found along any of the shortest paths
+	pub fn states(&self) -> HashSet<S> {
+		let mut seen:HashSet<S> = self.goals.iter().cloned().collect();
+		let mut pending:Vec<S> = self.goals.clone();
+		while let Some(state) = pending.pop() {
+			for parent in &self.visits[&state].parents {
+				if seen.insert(parent.clone()) {
+					pending.push(parent.clone());
+				}
+			}
+		}
+		seen
+	}
+}
+
+/// Records a new way of reaching `next`, returning true when it
+/// improves over the best one known, and must be explored
+fn relax<S:Clone+Eq+Hash>(visits:&mut HashMap<S,Visit<S>>, from:&S, next:S, cost:usize) -> bool {
+	match visits.get_mut(&next) {
+		Some(visit) if cost > visit.cost => false,
+		Some(visit) if cost == visit.cost => {
+			visit.parents.push(from.clone());
+			false
+		}
+		_ => {
+			visits.insert(next, Visit { cost, parents: vec![from.clone()] });
+			true
+		}
+	}
+}
+
+/// Breadth first search, for graphs where every step costs 1
+pub fn bfs<S,I>(start:S, mut successors:impl FnMut(&S) -> I, mut goal:impl FnMut(&S) -> bool) -> Option<Solution<S>>
+where
+	S: Clone+Eq+Hash,
+	I: IntoIterator<Item=S>,
+{
+	let mut visits = HashMap::from([(start.clone(), Visit { cost: 0, parents: vec![] })]);
+	let mut queue = VecDeque::from([start]);
+	let mut goals = vec![];
+	let mut best = None;
+
+	while let Some(state) = queue.pop_front() {
+
+		let cost = visits[&state].cost;
+
+		// states further away than the goals need no exploring
+		if best.is_some_and(|b| cost > b) { break }
+
+		if goal(&state) {
+			best = Some(cost);
+			goals.push(state);
+			continue
+		}
+
+		for next in successors(&state) {
+			if relax(&mut visits, &state, next.clone(), cost+1) {
+				queue.push_back(next);
+			}
+		}
+	}
+
+	best.map(|cost| Solution { cost, goals, visits })
+}
+
+/// Pending state in the priority queue, ordered by
+/// estimated total cost first, and then by cost so far
+struct Entry<S> {
+	estimate: usize,
+	cost: usize,
+	state: S,
+}
+
+impl<S> PartialEq for Entry<S> {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+
+impl<S> Eq for Entry<S> {}
+
+impl<S> PartialOrd for Entry<S> {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl<S> Ord for Entry<S> {
+	fn cmp(&self, other: &Self) -> Ordering {
+		// reversed, so that the max-heap pops the cheapest entry
+		(other.estimate,other.cost).cmp(&(self.estimate,self.cost))
+	}
+}
+
+/// A* search. The heuristic must never overestimate the cost
+/// left to reach a goal, and be consistent, for the shortest
+/// paths to be found.
+pub fn astar<S,I>(
+	start:S,
+	mut successors:impl FnMut(&S) -> I,
+	mut heuristic:impl FnMut(&S) -> usize,
+	mut goal:impl FnMut(&S) -> bool
+) -> Option<Solution<S>>
+where
+	S: Clone+Eq+Hash,
+	I: IntoIterator<Item=(S,usize)>,
+{
+	let mut visits = HashMap::from([(start.clone(), Visit { cost: 0, parents: vec![] })]);
+	let mut queue = BinaryHeap::from([Entry { estimate: heuristic(&start), cost: 0, state: start }]);
+	let mut goals = vec![];
+	let mut best = None;
+
+	while let Some(Entry { estimate, cost, state }) = queue.pop() {
+
+		// states that can't lead to a shortest path need no exploring
+		if best.is_some_and(|b| estimate > b) { break }
+
+		// stale entry, superseded by a cheaper one
+		if cost > visits[&state].cost { continue }
+
+		if goal(&state) {
+			best = Some(cost);
+			goals.push(state);
+			continue
+		}
+
+		for (next,step) in successors(&state) {
+			let cost = cost + step;
+			if relax(&mut visits, &state, next.clone(), cost) {
+				let estimate = cost + heuristic(&next);
+				queue.push(Entry { estimate, cost, state: next });
+			}
+		}
+	}
+
+	best.map(|cost| Solution { cost, goals, visits })
+}
+
+/// Dijkstra's search, for graphs with non-negative step costs
+pub fn dijkstra<S,I>(start:S, successors:impl FnMut(&S) -> I, goal:impl FnMut(&S) -> bool) -> Option<Solution<S>>
+where
+	S: Clone+Eq+Hash,
+	I: IntoIterator<Item=(S,usize)>,
+{
+	astar(start, successors, |_| 0, goal)
+}
+
+/// Cost of the shortest paths from the start to every reachable state
+pub fn costs<S,I>(start:S, mut successors:impl FnMut(&S) -> I) -> HashMap<S,usize>
+where
+	S: Clone+Eq+Hash,
+	I: IntoIterator<Item=(S,usize)>,
+{
+	let mut visits = HashMap::from([(start.clone(), Visit { cost: 0, parents: vec![] })]);
+	let mut queue = BinaryHeap::from([Entry { estimate: 0, cost: 0, state: start }]);
+
+	while let Some(Entry { cost, state, .. }) = queue.pop() {
+
+		if cost > visits[&state].cost { continue }
+
+		for (next,step) in successors(&state) {
+			let cost = cost + step;
+			if relax(&mut visits, &state, next.clone(), cost) {
+				queue.push(Entry { estimate: cost, cost, state: next });
+			}
+		}
+	}
+
+	visits.into_iter().map(|(state,visit)| (state,visit.cost)).collect()
+}
+
+/// A location on a map, and the direction faced there
+pub type Pose = (Position,Direction);
+
+impl<T:Copy> Map<T> {
+
+	/// Orthogonal neighbors of a position, whose cells are open
+	pub fn open_neighbors(&self, p:Position, open:impl Fn(T) -> bool) -> impl Iterator<Item=Position> {
+		self.neighbors_4(p).filter(move |&n| open(self[n]))
+	}
+
+	/// Shortest paths between two positions, moving
+	/// orthogonally through the cells that are open
+	pub fn shortest_paths(&self, from:Position, to:Position, open:impl Fn(T) -> bool) -> Option<Solution<Position>> {
+		astar(
+			from,
+			|&p| self.open_neighbors(p, &open).map(|n| (n,1)).collect::<Vec<_>>(),
+			|&p| p.x.abs_diff(to.x) + p.y.abs_diff(to.y),
+			|&p| p == to
+		)
+	}
+
+	/// Shortest paths from a pose to a position, moving forward through
+	/// open cells at a cost of `step`, or rotating 90 degrees in place
+	/// at a cost of `turn`
+	pub fn steer(&self, from:Pose, to:Position, step:usize, turn:usize, open:impl Fn(T) -> bool) -> Option<Solution<Pose>> {
+
+		let successors = |&(p,d):&Pose| {
+			let (left,right) = (d.turn_left(),d.turn_right());
+			let ahead = self.step(p, d.into())
+				.filter(|&n| open(self[n]))
+				.map(|n| ((n,d),step));
+			[((p,left),turn), ((p,right),turn)].into_iter().chain(ahead)
+		};
+
+		dijkstra(from, successors, |&(p,_)| p == to)
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use itertools::Itertools;
+
+	use super::*;
+
+	// Two routes of equal cost from 0 to 3, and a longer one
+	//
+	//   0 -1-> 1 -1-> 3
+	//   0 -1-> 2 -1-> 3
+	//   0 -5-> 3
+	fn graph(n:&u8) -> Vec<(u8,usize)> {
+		match n {
+			0 => vec![(1,1),(2,1),(3,5)],
+			1 => vec![(3,1)],
+			2 => vec![(3,1)],
+			_ => vec![],
+		}
+	}
+
+	#[test]
+	fn weighted() {
+
+		let solution = dijkstra(0, graph, |&n| n == 3).unwrap();
+
+		assert_eq!(solution.cost, 2);
+		assert_eq!(solution.path().len(), 3);
+
+		let paths = solution.paths().sorted().collect_vec();
+		assert_eq!(paths, vec![vec![0,1,3],vec![0,2,3]]);
+
+		assert_eq!(solution.states().len(), 4);
+
+		assert!(dijkstra(0, graph, |&n| n == 4).is_none());
+
+		let costs = costs(0, graph);
+		assert_eq!(costs[&3], 2);
+		assert_eq!(costs.len(), 4);
+	}
+
+	#[test]
+	fn unweighted() {
+
+		let successors = |n:&u8| graph(n).into_iter().map(|(n,_)| n);
+
+		// The direct edge is the shortest path when every step costs 1
+		let solution = bfs(0, successors, |&n| n == 3).unwrap();
+		assert_eq!(solution.cost, 1);
+		assert_eq!(solution.paths().collect_vec(), vec![vec![0,3]]);
+	}
+
+	const MAZE:&str = "\
+#######
+#....E#
+#.#.#.#
+#S....#
+#######";
+
+	#[test]
+	fn grid() {
+
+		let maze:Map = Map::read(MAZE.lines()).unwrap();
+		let start = maze.find(|c| c == b'S').unwrap();
+		let end = maze.find(|c| c == b'E').unwrap();
+		let open = |c| c != b'#';
+
+		let solution = maze.shortest_paths(start, end, open).unwrap();
+		assert_eq!(solution.cost, 6);
+		assert_eq!(solution.paths().count(), 3);
+		assert_eq!(solution.states().len(), 13);
+
+		// Facing east, going up at the end saves a turn
+		let solution = maze.steer((start,Direction::East), end, 1, 1000, open).unwrap();
+		assert_eq!(solution.cost, 1006);
+		assert_eq!(solution.paths().count(), 1);
+		assert_eq!(solution.path().last().unwrap().0, end);
+	}
+}
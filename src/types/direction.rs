@@ -1,6 +1,6 @@
 use super::*;
 
-#[derive(Debug,Clone,Copy,PartialEq,Eq,Ord,PartialOrd)]
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Ord,PartialOrd,Hash)]
 pub enum Direction {
 	North,
 	East,
@@ -8,10 +8,68 @@ pub enum Direction {
 	West,
 }
 
-impl Into<V2> for Direction {
-	fn into(self) -> V2 {
+impl Direction {
+
+	/// Every direction, clockwise from north
+	pub const ALL:[Direction;4] = {
+		use Direction::*;
+		[North,East,South,West]
+	};
+
+	/// The direction after rotating 90 degrees clockwise
+	pub const fn turn_right(self) -> Self {
 		use Direction::*;
 		match self {
+			North => East,
+			East  => South,
+			South => West,
+			West  => North,
+		}
+	}
+
+	/// The direction after rotating 90 degrees counterclockwise
+	pub const fn turn_left(self) -> Self {
+		self.opposite().turn_right()
+	}
+
+	pub const fn opposite(self) -> Self {
+		self.turn_right().turn_right()
+	}
+}
+
+/// Arrow pointing in the direction
+impl From<Direction> for char {
+	fn from(direction: Direction) -> Self {
+		use Direction::*;
+		match direction {
+			North => '^',
+			East  => '>',
+			South => 'v',
+			West  => '<',
+		}
+	}
+}
+
+/// Reads arrows (`^>v<`), compass points (`NESW`) or moves (`UDLR`)
+impl TryFrom<char> for Direction {
+	type Error = ();
+
+	fn try_from(c: char) -> Result<Self,Self::Error> {
+		use Direction::*;
+		match c {
+			'^' | 'N' | 'U' => Ok(North),
+			'>' | 'E' | 'R' => Ok(East),
+			'v' | 'S' | 'D' => Ok(South),
+			'<' | 'W' | 'L' => Ok(West),
+			_ => Err(())
+		}
+	}
+}
+
+impl From<Direction> for V2 {
+	fn from(direction: Direction) -> Self {
+		use Direction::*;
+		match direction {
 			North => V2{x: 0,y:-1},
 			East  => V2{x: 1,y: 0},
 			South => V2{x: 0,y: 1},
@@ -19,3 +77,116 @@ impl Into<V2> for Direction {
 		}
 	}
 }
+
+/// The eight points of the compass, including the diagonals
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Ord,PartialOrd,Hash)]
+pub enum Compass {
+	North,
+	NorthEast,
+	East,
+	SouthEast,
+	South,
+	SouthWest,
+	West,
+	NorthWest,
+}
+
+impl Compass {
+
+	/// Every point, clockwise from north
+	pub const ALL:[Compass;8] = {
+		use Compass::*;
+		[North,NorthEast,East,SouthEast,South,SouthWest,West,NorthWest]
+	};
+
+	/// The point after rotating 45 degrees clockwise
+	pub const fn turn_right(self) -> Self {
+		Self::ALL[(self as usize + 1) % 8]
+	}
+
+	/// The point after rotating 45 degrees counterclockwise
+	pub const fn turn_left(self) -> Self {
+		Self::ALL[(self as usize + 7) % 8]
+	}
+
+	pub const fn opposite(self) -> Self {
+		Self::ALL[(self as usize + 4) % 8]
+	}
+
+	pub const fn is_diagonal(self) -> bool {
+		self as usize % 2 == 1
+	}
+}
+
+impl From<Direction> for Compass {
+	fn from(direction: Direction) -> Self {
+		Self::ALL[direction as usize * 2]
+	}
+}
+
+/// Only the orthogonal points have a matching direction
+impl TryFrom<Compass> for Direction {
+	type Error = ();
+
+	fn try_from(point: Compass) -> Result<Self,Self::Error> {
+		if point.is_diagonal() { Err(()) } else { Ok(Direction::ALL[point as usize / 2]) }
+	}
+}
+
+impl From<Compass> for V2 {
+	fn from(point: Compass) -> Self {
+		use Compass::*;
+		match point {
+			North     => V2{x: 0,y:-1},
+			NorthEast => V2{x: 1,y:-1},
+			East      => V2{x: 1,y: 0},
+			SouthEast => V2{x: 1,y: 1},
+			South     => V2{x: 0,y: 1},
+			SouthWest => V2{x:-1,y: 1},
+			West      => V2{x:-1,y: 0},
+			NorthWest => V2{x:-1,y:-1},
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn direction() {
+
+		use Direction::*;
+
+		assert_eq!(North.turn_right(), East);
+		assert_eq!(North.turn_left(), West);
+		assert_eq!(East.opposite(), West);
+		assert!(Direction::ALL.into_iter().all(|d| d.turn_left().turn_right() == d));
+
+		let parsed:Vec<Direction> = "^>v<NESWURDL".chars().map(|c| c.try_into().unwrap()).collect();
+		assert_eq!(parsed, [Direction::ALL;3].concat());
+		assert!(Direction::try_from('x').is_err());
+
+		// arrows round trip
+		assert!(Direction::ALL.into_iter().all(|d| Direction::try_from(char::from(d)) == Ok(d)));
+	}
+
+	#[test]
+	fn compass() {
+
+		use Compass::*;
+
+		assert_eq!(North.turn_right(), NorthEast);
+		assert_eq!(North.turn_left(), NorthWest);
+		assert_eq!(SouthWest.opposite(), NorthEast);
+
+		assert_eq!(Compass::from(Direction::West), West);
+		assert_eq!(Direction::try_from(South), Ok(Direction::South));
+		assert!(Direction::try_from(SouthEast).is_err());
+
+		// opposite points have opposite vectors
+		assert!(Compass::ALL.into_iter().all(|p| V2::from(p) == -V2::from(p.opposite())));
+		assert!(Direction::ALL.into_iter().all(|d| V2::from(d) == V2::from(Compass::from(d))));
+	}
+}
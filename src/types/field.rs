@@ -1,44 +1,78 @@
 use super::{Position, V2};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Field {
-	pub height: u16,
-	pub width: u16,
+	pub height: usize,
+	pub width: usize,
 }
 
-impl From<(u16,u16)> for Field {
-	fn from(size: (u16,u16)) -> Self {
+impl From<(usize,usize)> for Field {
+	fn from(size: (usize,usize)) -> Self {
 		Self { width: size.0, height: size.1 }
 	}
 }
 
 impl Field {
 
-	fn len(&self) -> usize {
-		(self.width*self.height) as usize
+	/// Amount of positions in the field
+	pub fn area(&self) -> usize {
+		self.width*self.height
 	}
 
-	pub fn size(&self)->(u16,u16) {
+	pub fn size(&self)->(usize,usize) {
 		(self.width,self.height)
 	}
 
 	#[inline]
 	pub fn stride(&self) -> usize {
-		self.width as usize
+		self.width
 	}
 
 	pub fn contains(&self, coord:V2) -> bool {
-		let V2{x,y} = coord;
-		x >= 0 && y >= 0 &&
-		x < (self.width  as i32) &&
-		y < (self.height as i32)
+		Position::try_from(coord).is_ok_and(|p| self.contains_position(p))
+	}
+
+	pub fn contains_position(&self, p:Position) -> bool {
+		p.x < self.width && p.y < self.height
+	}
+
+	/// Returns the position at a given displacement from another,
+	/// if it falls inside the field, and within reach of `V2`
+	pub fn step(&self, p:Position, delta:V2) -> Option<Position> {
+		let coord = V2::try_from(p).ok()?.checked_add(delta)?;
+		if self.contains(coord) { coord.try_into().ok() } else { None }
+	}
+
+	/// Iterator of positions, starting at `from`, and repeatedly
+	/// displaced by `delta` while they remain inside the field
+	pub fn ray(&self, from:Position, delta:V2) -> impl Iterator<Item=Position> + use<> {
+		let field = *self;
+		let first = Some(from).filter(|&p| field.contains_position(p));
+		std::iter::successors(first, move |&p| field.step(p, delta))
+	}
+
+	/// Iterator of all the positions, row by row
+	pub fn positions(&self) -> impl Iterator<Item=Position> + use<> {
+		let Field { width, height } = *self;
+		(0..height).flat_map(move |y| (0..width).map(move |x| Position{x,y}))
+	}
+
+	/// Returns the position of a coordinate in a field that wraps
+	/// around its edges, so that leaving it by one side comes back
+	/// through the other. There's none when the field is empty,
+	/// or too large for its size to be a `V2`.
+	pub fn wrap(&self, coord:V2) -> Option<Position> {
+		let size = V2::try_from(Position{x:self.width,y:self.height}).ok()?;
+		if size.x == 0 || size.y == 0 {
+			return None
+		}
+		coord.rem_euclid(size).try_into().ok()
 	}
 
 	pub fn offset_of(&self, p:Position) -> Option<usize> {
-		if p <= self.last_position() {
+		if self.contains_position(p) {
 			let Position{x,y} = p;
-			let stride:u16 = self.stride() as u16;
-			(y*stride+x).try_into().ok()
+			Some(y*self.stride()+x)
 		} else {
 			None
 		}
@@ -46,16 +80,14 @@ impl Field {
 
 	pub fn position_of(&self, offset:usize) -> Option<Position> {
 		let stride = self.stride();
-		if offset < self.len() {
-			let x = (offset % stride).try_into().ok()?;
-			let y = (offset / stride).try_into().ok()?;
-			Some(Position{x,y})
+		if offset < self.area() {
+			Some(Position{x: offset % stride, y: offset / stride})
 		} else {
 			None
 		}
 	}
 
 	pub fn last_position(&self) -> Position {
-		self.position_of(self.len()-1).unwrap()
+		self.position_of(self.area()-1).unwrap()
 	}
 }
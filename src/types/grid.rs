@@ -0,0 +1,74 @@
+use itertools::Itertools;
+
+use super::*;
+
+/// Cells addressed by coordinates, whether stored densely
+/// inside some bounds (`Map`), or sparsely anywhere (`Sparse`)
+pub trait Grid {
+	type Cell: Copy;
+
+	/// The cell at a coordinate, if there's one
+	fn cell(&self, at:V2) -> Option<Self::Cell>;
+
+	/// Puts a value in a cell, returning false when
+	/// the coordinate falls outside a bounded grid
+	fn set(&mut self, at:V2, value:Self::Cell) -> bool;
+
+	/// Every cell, along with its coordinate
+	fn cells(&self) -> impl Iterator<Item=(V2,Self::Cell)>;
+
+	/// Smallest rectangle holding every cell, as its top left
+	/// and bottom right corners, or `None` when there are no cells
+	fn bounds(&self) -> Option<(V2,V2)>;
+
+	/// Coordinates of the orthogonal neighbors (N,E,S,W) holding a cell
+	fn adjacent_4(&self, at:V2) -> impl Iterator<Item=V2> {
+		Direction::ALL.into_iter()
+			.map(move |d| at + d.into())
+			.filter(|&n| self.cell(n).is_some())
+	}
+
+	/// Coordinates of the orthogonal and diagonal neighbors holding a cell
+	fn adjacent_8(&self, at:V2) -> impl Iterator<Item=V2> {
+		Compass::ALL.into_iter()
+			.map(move |d| at + d.into())
+			.filter(|&n| self.cell(n).is_some())
+	}
+
+	/// Draws the cells within the bounds, one row per line,
+	/// using `blank` where there's no cell
+	fn render(&self, blank:char) -> String where Self::Cell: Into<char> {
+		let Some((min,max)) = self.bounds() else { return String::new() };
+		let rows = (min.y..=max.y).map(|y| {
+			(min.x..=max.x)
+				.map(|x| self.cell(V2{x,y}).map_or(blank, Into::into))
+				.collect::<String>()
+		});
+		rows.format("\n").to_string()
+	}
+}
+
+impl<T:Copy> Grid for Map<T> {
+	type Cell = T;
+
+	fn cell(&self, at:V2) -> Option<T> {
+		Position::try_from(at).ok().and_then(|p| self.get(p)).copied()
+	}
+
+	fn set(&mut self, at:V2, value:T) -> bool {
+		match Position::try_from(at).ok().and_then(|p| self.get_mut(p)) {
+			Some(cell) => { *cell = value; true }
+			None => false
+		}
+	}
+
+	/// Cells past the reach of `V2` coordinates are left out
+	fn cells(&self) -> impl Iterator<Item=(V2,T)> {
+		self.enumerate().filter_map(|(p,t)| Some((V2::try_from(p).ok()?,t)))
+	}
+
+	fn bounds(&self) -> Option<(V2,V2)> {
+		let last = (self.area() > 0).then(|| self.last_position())?;
+		V2::try_from(last).ok().map(|max| (V2::zero(), max))
+	}
+}
@@ -1,7 +1,11 @@
-use std::ops::{Deref, Index, IndexMut};
+use std::{convert::Infallible, fmt::Display, ops::{Deref, Index, IndexMut}};
 
+use itertools::Itertools;
+
+use crate::InputError;
 use super::*;
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Map<T=u8> {
 	field: Field,
 	data: Vec<T>
@@ -15,6 +19,98 @@ impl<T:Copy> Map<T> {
 	pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
 		self.data.iter_mut()
 	}
+
+	/// Iterator of every cell, along with its position
+	pub fn enumerate(&self) -> impl Iterator<Item=(Position,T)> {
+		self.field.positions().zip(self.iter())
+	}
+
+	/// Position of the first cell matching a predicate, row by row
+	pub fn find(&self, predicate:impl Fn(T) -> bool) -> Option<Position> {
+		self.enumerate().find_map(|(p,t)| Some(p).filter(|_| predicate(t)))
+	}
+
+	/// Positions of all the cells with a given value
+	pub fn positions_of(&self, value:T) -> impl Iterator<Item=Position> where T:PartialEq {
+		self.enumerate().filter_map(move |(p,t)| Some(p).filter(|_| t == value))
+	}
+
+	/// Cells along a row, left to right
+	pub fn row(&self, y:usize) -> impl Iterator<Item=T> {
+		self.line(Position{x:0,y}, V2{x:1,y:0})
+	}
+
+	/// Cells along a column, top to bottom
+	pub fn column(&self, x:usize) -> impl Iterator<Item=T> {
+		self.line(Position{x,y:0}, V2{x:0,y:1})
+	}
+
+	/// Cells starting at a position, stepping by `delta` until leaving the map
+	pub fn line(&self, from:Position, delta:V2) -> impl Iterator<Item=T> {
+		self.field.ray(from, delta).map(|p| self[p])
+	}
+
+	pub fn rows(&self) -> impl Iterator<Item=impl Iterator<Item=T>> {
+		(0..self.height).map(|y| self.row(y))
+	}
+
+	pub fn columns(&self) -> impl Iterator<Item=impl Iterator<Item=T>> {
+		(0..self.width).map(|x| self.column(x))
+	}
+
+	/// Diagonals running down and to the right (↘), starting with
+	/// the one at the bottom left corner
+	pub fn diagonals_down(&self) -> impl Iterator<Item=impl Iterator<Item=T>> {
+		let Field { width, height } = self.field;
+		let left = (0..height).rev().map(|y| Position{x:0,y});
+		let top = (1..width).map(|x| Position{x,y:0});
+		left.chain(top).map(|p| self.line(p, V2{x:1,y:1}))
+	}
+
+	/// Diagonals running up and to the right (↗), starting with
+	/// the one at the top left corner
+	pub fn diagonals_up(&self) -> impl Iterator<Item=impl Iterator<Item=T>> {
+		let Field { width, height } = self.field;
+		let left = (0..height).map(|y| Position{x:0,y});
+		let bottom = (1..width).map(move |x| Position{x,y:height.saturating_sub(1)});
+		left.chain(bottom).map(|p| self.line(p, V2{x:1,y:-1}))
+	}
+}
+
+impl<T> Map<T> {
+
+	/// Builds a map of the given size, computing each cell from its position
+	pub fn from_fn(field:Field, cell:impl FnMut(Position) -> T) -> Self {
+		Map { field, data: field.positions().map(cell).collect() }
+	}
+
+	pub fn get(&self, p:Position) -> Option<&T> {
+		self.field.offset_of(p).map(|offset| &self.data[offset])
+	}
+
+	pub fn get_mut(&mut self, p:Position) -> Option<&mut T> {
+		self.field.offset_of(p).map(|offset| &mut self.data[offset])
+	}
+
+	/// Positions of the orthogonal neighbors (N,E,S,W) inside the map
+	pub fn neighbors_4(&self, p:Position) -> impl Iterator<Item=Position> + use<T> {
+		let field = self.field;
+		Direction::ALL.into_iter().filter_map(move |d| field.step(p, d.into()))
+	}
+
+	/// Positions of the orthogonal and diagonal neighbors inside the map
+	pub fn neighbors_8(&self, p:Position) -> impl Iterator<Item=Position> + use<T> {
+		let field = self.field;
+		Compass::ALL.into_iter().filter_map(move |d| field.step(p, d.into()))
+	}
+}
+
+/// Renders one row per line, each cell as a single character
+impl<T:Copy+Into<char>> Display for Map<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let rows = self.rows().map(|row| row.map(Into::into).collect::<String>());
+		write!(f, "{}", rows.format("\n"))
+	}
 }
 
 impl<T> Deref for Map<T> {
@@ -25,14 +121,77 @@ impl<T> Deref for Map<T> {
 	}
 }
 
-impl<'a,T:From<u8>,L:Iterator<Item=&'a str>+Clone> From<L> for Map<T> {
-	fn from(lines: L) -> Self {
-		let mut rows = lines.map(str::bytes).peekable();
-		let width:u16 = rows.peek().unwrap().len().try_into().unwrap();
-		let data:Vec<T> = rows.flatten().map(T::from).collect();
-		let height:u16 = u16::try_from(data.len()).unwrap() / width;
-		let field:Field = (width,height).into();
-		Map { field, data }
+impl<T:From<u8>> Map<T> {
+
+	/// Reads a map from lines of text, one byte per cell
+	pub fn read<'a>(lines: impl Iterator<Item=&'a str>) -> Result<Self,InputError> {
+		Self::parse(lines, |b| Ok::<_,Infallible>(T::from(b)))
+	}
+}
+
+/// Where some special cells were found while parsing a map
+#[derive(Debug, Default, PartialEq)]
+pub struct Markers(Vec<(u8,Position)>);
+
+impl Markers {
+
+	/// Position of the first cell with the marker
+	pub fn get(&self, marker:u8) -> Option<Position> {
+		self.all(marker).next()
+	}
+
+	/// Positions of every cell with the marker, row by row
+	pub fn all(&self, marker:u8) -> impl Iterator<Item=Position> {
+		self.0.iter().filter(move |(m,_)| *m == marker).map(|&(_,p)| p)
+	}
+}
+
+impl<T> Map<T> {
+
+	/// Reads a map from lines of text, decoding each byte into a cell.
+	/// Every row must be as wide as the first one. Errors are located
+	/// by row and column, both starting at 1.
+	pub fn parse<'a,E:Display>(lines: impl Iterator<Item=&'a str>, decode: impl FnMut(u8) -> Result<T,E>) -> Result<Self,InputError> {
+		Self::parse_marked(lines, &[], decode).map(|(map,_)| map)
+	}
+
+	/// Like `parse`, also telling where the cells with any of the
+	/// `markers` bytes were found. These are decoded as any other.
+	pub fn parse_marked<'a,E:Display>(
+		lines: impl Iterator<Item=&'a str>,
+		markers: &[u8],
+		mut decode: impl FnMut(u8) -> Result<T,E>
+	) -> Result<(Self,Markers),InputError> {
+
+		let mut data = vec![];
+		let mut found = vec![];
+		let mut width = None;
+		let mut height = 0;
+
+		for (y,row) in lines.enumerate() {
+
+			let number = y+1;
+			let cells = row.as_bytes();
+			let width = *width.get_or_insert(cells.len());
+
+			if cells.len() != width {
+				let msg = format!("Expected {width} cells, found {}", cells.len());
+				return Err(InputError::new(number, cells.len().min(width) + 1, row, msg))
+			}
+
+			for (x,&b) in cells.iter().enumerate() {
+				if markers.contains(&b) { found.push((b,Position{x,y})) }
+				let cell = decode(b).map_err(|e| InputError::new(number, x+1, row, e.to_string()))?;
+				data.push(cell);
+			}
+
+			height += 1;
+		}
+
+		match width {
+			None | Some(0) => Err(InputError::new(1, 1, "", "Map should have at least one row")),
+			Some(width) => Ok((Map { field: (width,height).into(), data }, Markers(found))),
+		}
 	}
 }
 
@@ -45,6 +204,13 @@ impl<T> IntoIterator for Map<T> {
 	}
 }
 
+/// A map of a single row
+impl<T> From<Vec<T>> for Map<T> {
+	fn from(data:Vec<T>) -> Self {
+		Map { field: (data.len(),1).into(), data }
+	}
+}
+
 impl<T> Index<Position> for Map<T> {
 	type Output = T;
 	fn index(&self, p: Position) -> &Self::Output {
@@ -59,3 +225,96 @@ impl<T> IndexMut<Position> for Map<T> {
 		&mut self.data[offset]
 	}
 }
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	const INPUT:&str = "ABCD\nEFGH\nIJKL";
+
+	fn string(cells:impl Iterator<Item=u8>) -> String {
+		cells.map(char::from).collect()
+	}
+
+	#[test]
+	fn parse() {
+
+		let digit = |b:u8| if b.is_ascii_digit() { Ok(b - b'0') } else { Err("Expected a digit") };
+
+		let map = Map::parse("12\n34".lines(), digit).unwrap();
+		assert_eq!(map.iter().collect_vec(), vec![1,2,3,4]);
+
+		let err = Map::parse("12\n3x".lines(), digit).unwrap_err();
+		assert_eq!((err.line,err.column,err.reason.as_str()), (2,2,"Expected a digit"));
+
+		// ragged rows
+		let err = Map::<u8>::read("123\n12\n123".lines()).unwrap_err();
+		assert_eq!((err.line,err.column), (2,3));
+		let err = Map::<u8>::read("12\n123".lines()).unwrap_err();
+		assert_eq!((err.line,err.column), (2,3));
+
+		assert!(Map::<u8>::read("".lines()).is_err());
+
+		let (map,markers) = Map::parse_marked("S.#\n.#E\n..E".lines(), b"SE", |b| Ok::<_,Infallible>(b == b'#')).unwrap();
+		assert_eq!(map.iter().filter(|&wall| wall).count(), 2);
+		assert_eq!(markers.get(b'S'), Some(Position{x:0,y:0}));
+		assert_eq!(markers.all(b'E').collect_vec(), vec![Position{x:2,y:1},Position{x:2,y:2}]);
+		assert_eq!(markers.get(b'#'), None);
+	}
+
+	#[test]
+	fn large() {
+
+		let row = ".".repeat(1000);
+		let map:Map = Map::read(std::iter::repeat_n(row.as_str(), 1000)).unwrap();
+
+		assert_eq!(map.size(), (1000,1000));
+		assert_eq!(map.last_position(), Position{x:999,y:999});
+		assert_eq!(map.offset_of(map.last_position()), Some(999_999));
+		assert_eq!(map.get(Position{x:999,y:500}), Some(&b'.'));
+		assert_eq!(map.get(Position{x:1000,y:500}), None);
+	}
+
+	#[test]
+	fn grid() {
+
+		let map:Map = Map::read(INPUT.lines()).unwrap();
+
+		assert_eq!(map.to_string(), INPUT);
+
+		assert_eq!(map.get(Position{x:3,y:2}), Some(&b'L'));
+		assert_eq!(map.get(Position{x:4,y:0}), None);
+		assert_eq!(map.get(Position{x:0,y:3}), None);
+
+		assert_eq!(map.find(|c| c == b'G'), Some(Position{x:2,y:1}));
+		assert_eq!(map.positions_of(b'Z').next(), None);
+
+		let (p,c) = map.enumerate().nth(5).unwrap();
+		assert_eq!((p,c), (Position{x:1,y:1}, b'F'));
+
+		// corners only have 2 orthogonal neighbors
+		let actual = map.neighbors_4(Position::zero()).collect_vec();
+		assert_eq!(actual, vec![Position{x:1,y:0},Position{x:0,y:1}]);
+		assert_eq!(map.neighbors_8(Position::zero()).count(), 3);
+		assert_eq!(map.neighbors_8(Position{x:1,y:1}).count(), 8);
+
+		// leaving through a corner comes back through the opposite one
+		assert_eq!(map.wrap(V2{x:-1,y:-1}), Some(map.last_position()));
+		assert_eq!(map.wrap(V2{x:9,y:3}), Some(Position{x:1,y:0}));
+		assert_eq!(Field { width: 0, height: 3 }.wrap(V2{x:1,y:1}), None);
+		assert_eq!(Field { width: 1 << 40, height: 3 }.wrap(V2{x:1,y:1}), None);
+
+		assert_eq!(string(map.row(1)), "EFGH");
+		assert_eq!(string(map.column(2)), "CGK");
+
+		let actual = map.columns().map(string).collect_vec();
+		assert_eq!(actual, vec!["AEI","BFJ","CGK","DHL"]);
+
+		let actual = map.diagonals_down().map(string).collect_vec();
+		assert_eq!(actual, vec!["I","EJ","AFK","BGL","CH","D"]);
+
+		let actual = map.diagonals_up().map(string).collect_vec();
+		assert_eq!(actual, vec!["A","EB","IFC","JGD","KH","L"]);
+	}
+}
@@ -2,6 +2,9 @@ mod position;
 mod direction;
 mod field;
 mod map;
+mod grid;
+mod sparse;
+mod view;
 mod v2;
 
 pub(crate) use {
@@ -9,5 +12,13 @@ pub(crate) use {
 	direction::*,
 	field::*,
 	map::*,
+	grid::*,
 	v2::*,
 };
+
+// a grid solvers may use instead of `Map`, though none does yet
+#[allow(unused_imports)]
+pub(crate) use sparse::Sparse;
+
+#[cfg(test)]
+pub(crate) use view::assert_grid_eq;
@@ -2,7 +2,7 @@ use std::fmt::Display;
 use super::*;
 
 #[derive(Debug,Clone,Copy,PartialEq,Eq,Default,Ord,PartialOrd,Hash)]
-pub struct Position { pub x:u16, pub y:u16 }
+pub struct Position { pub x:usize, pub y:usize }
 
 impl Display for Position {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -16,9 +16,9 @@ impl Position {
 	}
 }
 
-impl Into<(u16,u16)> for Position {
-	fn into(self) -> (u16,u16) {
-		(self.x,self.y)
+impl From<Position> for (usize,usize) {
+	fn from(pos: Position) -> Self {
+		(pos.x,pos.y)
 	}
 }
 
@@ -26,10 +26,9 @@ impl TryFrom<V2> for Position {
 	type Error = ();
 	fn try_from(v2: V2) -> Result<Self,Self::Error> {
 		let V2{x,y} = v2;
-		if x >= 0 && y >= 0 {
-			Ok(Position{x: x as u16, y: y as u16})
-		} else {
-			Err(())
+		match (usize::try_from(x), usize::try_from(y)) {
+			(Ok(x), Ok(y)) => Ok(Position{x,y}),
+			_ => Err(())
 		}
 	}
 }
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use itertools::{Itertools, MinMaxResult};
+
+use super::*;
+
+/// A grid without bounds, storing only the cells that have been set,
+/// for anything that may grow in any direction
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sparse<T=u8>(HashMap<V2,T>);
+
+impl<T> Sparse<T> {
+
+	pub fn new() -> Self {
+		Self(HashMap::new())
+	}
+
+	pub fn len(&self) -> usize {
+		self.0.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	pub fn remove(&mut self, at:V2) -> Option<T> {
+		self.0.remove(&at)
+	}
+}
+
+impl<T:From<u8>> Sparse<T> {
+
+	/// Reads a grid from lines of text, one byte per cell,
+	/// with the first one at the origin
+	pub fn read<'a>(lines: impl Iterator<Item=&'a str>) -> Self {
+		lines
+			.zip(0..)
+			.flat_map(|(row,y)| row.bytes().zip(0..).map(move |(b,x)| (V2{x,y}, T::from(b))))
+			.collect()
+	}
+}
+
+impl<T> FromIterator<(V2,T)> for Sparse<T> {
+	fn from_iter<I: IntoIterator<Item=(V2,T)>>(cells: I) -> Self {
+		Self(cells.into_iter().collect())
+	}
+}
+
+impl<T:Copy> Grid for Sparse<T> {
+	type Cell = T;
+
+	fn cell(&self, at:V2) -> Option<T> {
+		self.0.get(&at).copied()
+	}
+
+	fn set(&mut self, at:V2, value:T) -> bool {
+		self.0.insert(at, value);
+		true
+	}
+
+	fn cells(&self) -> impl Iterator<Item=(V2,T)> {
+		self.0.iter().map(|(&at,&t)| (at,t))
+	}
+
+	fn bounds(&self) -> Option<(V2,V2)> {
+		let corners = |xs:MinMaxResult<i32>, ys:MinMaxResult<i32>| {
+			let ((x0,x1),(y0,y1)) = (xs.into_option()?,ys.into_option()?);
+			Some((V2{x:x0,y:y0},V2{x:x1,y:y1}))
+		};
+		corners(self.0.keys().map(|v| v.x).minmax(), self.0.keys().map(|v| v.y).minmax())
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	const INPUT:&str = "\
+A.
+.B";
+
+	#[test]
+	fn sparse() {
+
+		let mut grid:Sparse = Sparse::read(INPUT.lines());
+		let map:Map = Map::read(INPUT.lines()).unwrap();
+
+		// both kinds of grid look the same
+		assert_eq!(grid.bounds(), map.bounds());
+		assert_eq!(grid.render(' '), map.render(' '));
+		assert_eq!(grid.cells().sorted().collect_vec(), map.cells().sorted().collect_vec());
+		assert_eq!(grid.adjacent_8(V2::zero()).count(), map.adjacent_8(V2::zero()).count());
+
+		// ...until they grow
+		assert!(grid.set(V2{x:-2,y:-1}, b'C'));
+		assert!(!map.clone().set(V2{x:-2,y:-1}, b'C'));
+
+		assert_eq!(grid.bounds(), Some((V2{x:-2,y:-1},V2{x:1,y:1})));
+		assert_eq!(grid.render(' '), "C   \n  A.\n  .B");
+
+		grid.remove(V2{x:0,y:1});
+		assert_eq!(grid.adjacent_4(V2::zero()).collect_vec(), vec![V2{x:1,y:0}]);
+
+		assert_eq!(Sparse::<u8>::new().bounds(), None);
+		assert_eq!(Sparse::<u8>::new().render('.'), "");
+	}
+}
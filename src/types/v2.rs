@@ -1,7 +1,7 @@
-use std::ops::{Add, Neg, Sub};
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
 use super::*;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct V2 { pub x:i32, pub y:i32 }
 
 
@@ -9,6 +9,38 @@ impl V2 {
 	pub fn zero() -> Self {
 		Self{x:0,y:0}
 	}
+
+	/// Distance moving orthogonally
+	pub fn manhattan(self, other:V2) -> u32 {
+		self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+	}
+
+	/// Distance moving orthogonally or diagonally
+	pub fn chebyshev(self, other:V2) -> u32 {
+		self.x.abs_diff(other.x).max(self.y.abs_diff(other.y))
+	}
+
+	/// The shortest vector with the same direction, having integer
+	/// components: every point along it is a multiple of this one
+	pub fn reduced(self) -> Self {
+		fn gcd(a:u32, b:u32) -> u32 {
+			if b == 0 { a } else { gcd(b, a % b) }
+		}
+		match gcd(self.x.unsigned_abs(), self.y.unsigned_abs()) {
+			0 => self,
+			n => self / n as i32,
+		}
+	}
+
+	/// Sum of two vectors, or `None` when a component overflows
+	pub fn checked_add(self, other:V2) -> Option<Self> {
+		Some(V2{x:self.x.checked_add(other.x)?,y:self.y.checked_add(other.y)?})
+	}
+
+	/// Wraps each component into `0..size`, as if space looped around
+	pub fn rem_euclid(self, size:V2) -> Self {
+		V2{x:self.x.rem_euclid(size.x),y:self.y.rem_euclid(size.y)}
+	}
 }
 
 impl Neg for V2 {
@@ -37,9 +69,90 @@ impl Sub for V2 {
 	}
 }
 
-impl From<Position> for V2 {
-	fn from(pos: Position) -> Self {
+impl AddAssign for V2 {
+	fn add_assign(&mut self, rhs: Self) {
+		*self = *self + rhs;
+	}
+}
+
+impl SubAssign for V2 {
+	fn sub_assign(&mut self, rhs: Self) {
+		*self = *self - rhs;
+	}
+}
+
+impl Mul<i32> for V2 {
+	type Output = V2;
+
+	fn mul(self, rhs: i32) -> Self::Output {
+		V2{x:self.x*rhs,y:self.y*rhs}
+	}
+}
+
+/// Integer division of each component, rounding towards zero
+impl Div<i32> for V2 {
+	type Output = V2;
+
+	fn div(self, rhs: i32) -> Self::Output {
+		V2{x:self.x/rhs,y:self.y/rhs}
+	}
+}
+
+impl TryFrom<Position> for V2 {
+	type Error = ();
+	fn try_from(pos: Position) -> Result<Self,Self::Error> {
 		let Position{x,y} = pos;
-		V2{x:x as i32, y:y as i32}
+		match (i32::try_from(x), i32::try_from(y)) {
+			(Ok(x), Ok(y)) => Ok(V2{x,y}),
+			_ => Err(())
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn algebra() {
+
+		let mut v = V2{x:3,y:-6};
+
+		assert_eq!(v * 2, V2{x:6,y:-12});
+		assert_eq!(v / 2, V2{x:1,y:-3});
+
+		v += V2{x:1,y:1};
+		assert_eq!(v, V2{x:4,y:-5});
+		v -= V2{x:1,y:1};
+		assert_eq!(v, V2{x:3,y:-6});
+
+		let o = V2::zero();
+		assert_eq!(v.manhattan(o), 9);
+		assert_eq!(v.chebyshev(o), 6);
+
+		assert_eq!(v.reduced(), V2{x:1,y:-2});
+		assert_eq!(V2{x:0,y:-4}.reduced(), V2{x:0,y:-1});
+		assert_eq!(o.reduced(), o);
+
+		let size = V2{x:11,y:7};
+		assert_eq!(V2{x:-1,y:15}.rem_euclid(size), V2{x:10,y:1});
+	}
+
+	#[test]
+	fn overflow() {
+
+		let far = Position{x:1 << 40,y:0};
+		assert_eq!(V2::try_from(Position{x:3,y:4}), Ok(V2{x:3,y:4}));
+		assert!(V2::try_from(far).is_err());
+
+		let edge = V2{x:i32::MAX,y:0};
+		assert_eq!(edge.checked_add(V2{x:-1,y:1}), Some(V2{x:i32::MAX-1,y:1}));
+		assert_eq!(edge.checked_add(V2{x:1,y:0}), None);
+
+		let field = Field { width: usize::MAX, height: 1 };
+		assert_eq!(field.step(Position{x:i32::MAX as usize,y:0}, V2{x:1,y:0}), None);
+		assert_eq!(field.step(far, V2{x:1,y:0}), None);
+		assert_eq!(field.step(Position{x:1,y:0}, V2{x:-1,y:0}), Some(Position{x:0,y:0}));
 	}
 }
@@ -0,0 +1,121 @@
+use std::{collections::HashMap, fmt::Display};
+
+use itertools::Itertools;
+
+use super::*;
+
+/// A drawing of a map, one character per cell, see `Map::view`
+pub struct View<'a,T> {
+	map: &'a Map<T>,
+	cell: Box<dyn Fn(T) -> char + 'a>,
+	overlay: HashMap<Position,char>,
+}
+
+impl<T:Copy> Map<T> {
+
+	/// Draws the map, choosing a character for each cell
+	pub fn view<'a>(&'a self, cell:impl Fn(T) -> char + 'a) -> View<'a,T> {
+		View { map: self, cell: Box::new(cell), overlay: HashMap::new() }
+	}
+
+	/// Cells that differ between two maps, with the value in each.
+	/// Maps of different sizes are only compared where they overlap.
+	pub fn differences<'a>(&'a self, other:&'a Map<T>) -> impl Iterator<Item=(Position,T,T)> + 'a where T:PartialEq {
+		self.enumerate().filter_map(|(p,a)| {
+			other.get(p).copied().filter(|&b| b != a).map(|b| (p,a,b))
+		})
+	}
+}
+
+impl<T> View<'_,T> {
+
+	/// Draws some cells with characters of their own, over the map.
+	/// Later overlays are drawn over earlier ones.
+	pub fn overlay(mut self, cells:impl IntoIterator<Item=(Position,char)>) -> Self {
+		self.overlay.extend(cells);
+		self
+	}
+
+	/// Draws every given position with the same character
+	pub fn highlight(self, positions:impl IntoIterator<Item=Position>, mark:char) -> Self {
+		self.overlay(positions.into_iter().map(|p| (p,mark)))
+	}
+}
+
+/// Renders one row per line
+impl<T:Copy> Display for View<'_,T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let Field { width, height } = **self.map;
+		let rows = (0..height).map(|y| {
+			(0..width)
+				.map(|x| Position{x,y})
+				.map(|p| self.overlay.get(&p).copied().unwrap_or_else(|| (self.cell)(self.map[p])))
+				.collect::<String>()
+		});
+		write!(f, "{}", rows.format("\n"))
+	}
+}
+
+/// Compares two drawings of a grid, failing with a diff of the rows,
+/// and the coordinates of the first cells that differ.
+/// The expected drawing is `dedent`ed, and the blank lines around it are ignored.
+#[cfg(test)]
+#[track_caller]
+pub fn assert_grid_eq(actual:impl Display, expected:&str) {
+
+	use itertools::EitherOrBoth::*;
+
+	let rows = |s:&str| s.trim_matches('\n').split('\n').map(String::from).collect_vec();
+	let (actual,expected) = (rows(&actual.to_string()), rows(&crate::days::dedent(expected)));
+
+	let differences = actual.iter().zip_longest(&expected)
+		.enumerate()
+		.flat_map(|(y,rows)| {
+			let (a,e) = match rows { Both(a,e) => (a.as_str(),e.as_str()), Left(a) => (a.as_str(),""), Right(e) => ("",e.as_str()) };
+			a.chars().zip_longest(e.chars()).enumerate().filter_map(move |(x,cells)| match cells {
+				Both(a,e) if a == e => None,
+				Both(a,e) => Some(format!("{x},{y}: expected '{e}', found '{a}'")),
+				Left(a) => Some(format!("{x},{y}: expected nothing, found '{a}'")),
+				Right(e) => Some(format!("{x},{y}: expected '{e}', found nothing")),
+			})
+		})
+		.collect_vec();
+
+	pretty_assertions::assert_eq!(
+		actual.join("\n"),
+		expected.join("\n"),
+		"{} cell(s) differ, at {}",
+		differences.len(),
+		differences.iter().take(5).join("; ")
+	);
+}
+
+#[cfg(test)]
+mod test {
+
+	use super::*;
+
+	#[test]
+	fn view() {
+
+		let map:Map = Map::read("ab\ncd".lines()).unwrap();
+
+		let view = map.view(|c| c.to_ascii_uppercase() as char)
+			.highlight([Position{x:1,y:0},Position{x:0,y:1}], '*')
+			.overlay([(Position{x:1,y:0},'#')]);
+
+		assert_grid_eq(view, "
+			A#
+			*D
+		");
+
+		let other:Map = Map::read("ab\nxd".lines()).unwrap();
+		assert_eq!(map.differences(&other).collect_vec(), vec![(Position{x:0,y:1},b'c',b'x')]);
+	}
+
+	#[test]
+	#[should_panic(expected = "1 cell(s) differ, at 1,1: expected 'x', found 'd'")]
+	fn grid_diff() {
+		assert_grid_eq("ab\ncd", "ab\ncx");
+	}
+}